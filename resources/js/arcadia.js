@@ -1,5 +1,6 @@
 const MOD_MENU = "modMenu";
 const SUB_MENU = "subMenu";
+const DETAILS_MENU = "detailsMenu";
 const categories = [
     "All",
     "Fighter",
@@ -10,8 +11,6 @@ const categories = [
     "Audio",
     "Misc",
 ];
-var categoriesToUse = [];
-
 var currentState = MOD_MENU;
 
 
@@ -19,19 +18,42 @@ var LButtonHeld = false;
 var RButtonHeld = false;
 var AButtonHeld = false;
 var BButtonHeld = false;
+var XButtonHeld = false;
 
 var currentDescHeight = 0; // Used for the current position of the description (modified by the R-Stick Y Value).
 var currentActiveDescription // For reference to the current active description.
 var activeDescHeight = 0; // The height for the current active description so it can't be scrolled out of bounds.
 
-var mods = [];
-var currentMods = [];
+// Only the mods of the current page, by id. The whole list stays on the Rust side.
+var mods = {};
 var modSize = 0;
-var pageCount = 0;
+var modCount = 0;
+var enabledCount = 0;
+var currentPage = 1;
+var pageCount = 1;
+var pageSize = 10;
+// Which mod to focus once the requested page arrives, "first" or "last"
+var focusOnLoad = "first";
+
+// Enabled mods that share paths with other enabled mods, by id
+var conflicts = {};
+
+var searchText = "";
+var authorFilter = "";
+
+function sendMessage(message) {
+    if (isNx) {
+        window.nx.sendMessage(JSON.stringify(message));
+    } else {
+        fakeServer(message);
+    }
+}
 
-function createMod(mod_id) {
-    var hidden = mods[mod_id]['is_disabled'] ? "hidden" : "";
-    return `<button id="btn-mods-${mod_id}" data-mod-index="${mod_id}" tabindex="0" class="flex-button abstract-button All ${mods[mod_id]['category']}" nx-se-disabled="">
+function createMod(mod) {
+    var mod_id = mod['id'];
+    var hidden = mod['is_disabled'] ? "hidden" : "";
+    var conflicting = mod_id in conflicts ? "conflicting" : "";
+    return `<button id="btn-mods-${mod_id}" data-mod-index="${mod_id}" tabindex="0" class="flex-button abstract-button All ${mod['category']} ${conflicting}" nx-se-disabled="">
     <div class="abstract-icon-back-decoration"></div>
     <div class="abstract-button-border">
         <div class="abstract-button-inner">
@@ -41,35 +63,37 @@ function createMod(mod_id) {
                 </div>
             </div>
             <div class="abstract-button-text f-u-bold mod-name"
-                style="display: block; font-size: 26px; text-indent: 10px; margin-top: 8px;" data-display_name="${mods[mod_id]['display_name']}">
-                <span class="marquee" data-msgid="textbox_id-4-1">${mods[mod_id]['display_name']}</span>
+                style="display: block; font-size: 26px; text-indent: 10px; margin-top: 8px;" data-display_name="${mod['display_name']}">
+                <span class="marquee" data-msgid="textbox_id-4-1">${mod['display_name']}</span>
             </div>
         </div>
     </div>
 </button>`;
 }
 
-function createMods(mods) {
+function createMods(entries) {
     var res = "";
-    for (var i = 0; i < mods.length; i++) {
-        res += createMod(mods[i], i);
+    for (var i = 0; i < entries.length; i++) {
+        res += createMod(entries[i]);
     }
     return res;
 }
 
 function toggleMod() {
     var index = parseInt($(".is-focused").attr("data-mod-index"));
+    if (isNaN(index)) { return; }
     var checkContainer = $(".is-focused .img-check");
     checkContainer.toggleClass("hidden");
     var enabled = !checkContainer.hasClass("hidden");
     mods[index]["is_disabled"] = !enabled;
+    enabledCount += enabled ? 1 : -1;
     // Send mod index and status
-    window.nx.sendMessage(JSON.stringify({
+    sendMessage({
         "ToggleMod": {
             "id": index,
             "state": enabled
         }
-    }));
+    });
 }
 
 function updateCurrentDesc() {
@@ -100,6 +124,16 @@ function checkGamepad(index, gamepad) {
             showSubMenu();
         }
 
+        // X Button
+        if (gamepad.buttons[3].pressed) {
+            if (!XButtonHeld) {
+                requestDetails();
+                XButtonHeld = true;
+            }
+        } else {
+            XButtonHeld = false;
+        }
+
         if (gamepad.buttons[1].pressed) {
             if (!AButtonHeld) {
                 toggleMod();
@@ -160,6 +194,18 @@ function checkGamepad(index, gamepad) {
 
         currentActiveDescription.scrollTop(currentDescHeight);
         //#endregion
+    } else if (currentState == DETAILS_MENU) {
+        if (gamepad.buttons[0].pressed) {
+            if (!BButtonHeld) {
+                hideDetails();
+                BButtonHeld = true;
+            }
+        } else {
+            BButtonHeld = false;
+        }
+
+        var detailsScroll = gamepad.axes[3].toFixed(2) * 20 + gamepad.axes[1].toFixed(2) * 20;
+        $("#inner-details").scrollTop($("#inner-details").scrollTop() + detailsScroll);
     } else if (currentState == SUB_MENU) {
         // Handle sub menu controls
 
@@ -184,10 +230,11 @@ function moveUp() {
     }
 
     if (target == undefined) {
-        prevPage();
+        if (pageCount > 1) {
+            prevPage("last");
+            return;
+        }
         target = document.querySelector("#mods>button:last-child");
-        move(document.querySelector("#mods>button.is-focused"), target);
-        return;
     }
 
     move(source, target);
@@ -202,10 +249,11 @@ function moveDown() {
     }
 
     if (target == undefined) {
-        nextPage();
+        if (pageCount > 1) {
+            nextPage();
+            return;
+        }
         target = document.querySelector("#mods>button:first-child");
-        move(document.querySelector("#mods>button.is-focused"), target);
-        return;
     }
 
     move(source, target);
@@ -244,30 +292,71 @@ function move(source, target) {
         $("#version").html(mod["version"]);
         $("#authors").html(mod["authors"]);
         $("#preview").attr("src", `img/${mod['id']}`);
+        updateConflictNote(mod['id']);
         updateCurrentDesc();
     }
 }
 
-function nextPage() {
+function currentQuery() {
+    var categories = [];
+    $('#filters input:checkbox:checked').each(function(idx) {
+        categories.push($(this).attr('id'));
+    });
 
-    if ($('#mods').pagination("getTotalPage") <= 1) { return; }
+    return {
+        "search": searchText,
+        "categories": categories,
+        "author": authorFilter,
+        "sort": document.getElementById('sortOptions').value,
+        "descending": document.getElementById('desc').checked
+    };
+}
 
-    if ($('#mods').pagination("getSelectedPageNum") == $('#mods').pagination("getTotalPage")) {
-        $('#mods').pagination("go", 1);
-    } else {
-        $('#mods').pagination("next");
+function requestPage(page, focus) {
+    focusOnLoad = focus;
+    sendMessage({
+        "QueryPage": {
+            "query": currentQuery(),
+            "page": page,
+            "page_size": pageSize
+        }
+    });
+}
+
+function renderPage(page) {
+    mods = {};
+    page["entries"].forEach(mod => mods[mod["id"]] = mod);
+    currentPage = page["page"];
+    pageCount = page["page_count"];
+    modCount = page["mod_count"];
+    enabledCount = page["enabled_count"];
+
+    $("#mods").html(createMods(page["entries"]));
+    $("#pageInfo").html(`${currentPage} of ${pageCount}`);
+
+    if (page["entries"].length == 0) {
+        var categories = currentQuery()["categories"];
+        $("#description").html(categories.length == 0 ? "No mods found." : `No mods found under:<br />${categories.join("<br />")}`);
+        $("#version").html("");
+        $("#authors").html("");
+        $("#preview").attr("src", "missing.webp");
+        return;
     }
+
+    var target = focusOnLoad == "last" ? $("#mods>button").last().get(0) : $("#mods>button").get(0);
+    move(undefined, target);
 }
 
-function prevPage() {
+function nextPage() {
+    if (pageCount <= 1) { return; }
+
+    requestPage(currentPage == pageCount ? 1 : currentPage + 1, "first");
+}
 
-    if ($('#mods').pagination("getTotalPage") <= 1) { return; }
+function prevPage(focus) {
+    if (pageCount <= 1) { return; }
 
-    if ($('#mods').pagination("getSelectedPageNum") == 1) {
-        $('#mods').pagination("go", $('#mods').pagination("getTotalPage"));
-    } else {
-        $('#mods').pagination("previous");
-    }
+    requestPage(currentPage == 1 ? pageCount : currentPage - 1, focus || "first");
 }
 
 // yoinked from here https://stackoverflow.com/questions/143815/determine-if-an-html-elements-content-overflows
@@ -302,15 +391,39 @@ function sizeToFormattedBytes(size) {
     return `${size.toFixed(2)} gb`;
 }
 
-function showSubMenu() {
-    $("#modsCount").html(`${mods.length} mod${mods.length > 1 ? 's' : ''}`);
-    var activeMods = 0;
-    mods.forEach(mod => activeMods = mod["is_disabled"] ? activeMods : activeMods + 1);
-    $("#activeModsCount").html(`${activeMods} active mod${activeMods > 1 ? 's' : ''}`);
+function updateStats() {
+    $("#modsCount").html(`${modCount} mod${modCount > 1 ? 's' : ''}`);
+    $("#activeModsCount").html(`${enabledCount} active mod${enabledCount > 1 ? 's' : ''}`);
     if (modSize == 0)
         $("#modSize").html("")
     else
         $("#modSize").html(`${sizeToFormattedBytes(modSize)} of mods enabled`)
+}
+
+function editSearch() {
+    var text = prompt("Name or description to look for (leave empty for all mods)", searchText);
+
+    if (text === null) {
+        return;
+    }
+
+    searchText = text.trim();
+    $("#searchButton").html(`Name or description: ${searchText == "" ? "(any)" : searchText}`);
+}
+
+function editAuthor() {
+    var text = prompt("Author to look for (leave empty for all authors)", authorFilter);
+
+    if (text === null) {
+        return;
+    }
+
+    authorFilter = text.trim();
+    $("#authorButton").html(`Author: ${authorFilter == "" ? "(any)" : authorFilter}`);
+}
+
+function showSubMenu() {
+    updateStats();
 
     $("#submenu").css("display", "flex");
     $("#Fighter").focus();
@@ -318,127 +431,229 @@ function showSubMenu() {
     currentState = SUB_MENU;
 }
 
-function updateCurrentModsWCategories() {
-    categoriesToUse = [];
-    $('#filters input:checkbox:checked').each(function(idx) {
-        categoriesToUse.push($(this).attr('id'));
+function requestDetails() {
+    var index = parseInt($(".is-focused").attr("data-mod-index"));
+    if (isNaN(index)) { return; }
+
+    sendMessage({
+        "GetModDetails": {
+            "id": index
+        }
     });
-    currentMods = categoriesToUse.length == 0 ? mods.map(x => x["id"]) : mods.filter(mod => categoriesToUse.includes(mod["category"])).map(x => x["id"]);
 }
 
-function showModMenu() {
-    $("#submenu").css("display", "none");
-    document.querySelector('meta[name="focus-ring-visibility"]').setAttribute("content", "hidden");
-    updateCurrentModsWCategories();
-    if (currentMods.length == 0) {
-        $("#description").html(`No mods found under:<br />${categoriesToUse.join("<br />")}`);
+function updateConflicts(list) {
+    conflicts = {};
+    list.forEach(conflict => conflicts[conflict["id"]] = conflict);
+
+    $("#mods>button").each(function() {
+        $(this).toggleClass("conflicting", parseInt($(this).attr("data-mod-index")) in conflicts);
+    });
+
+    var focused = parseInt($("#mods>button.is-focused").attr("data-mod-index"));
+    if (!isNaN(focused)) {
+        updateConflictNote(focused);
     }
-    refreshCurrentMods();
-    currentState = MOD_MENU;
 }
 
-function refreshCurrentMods() {
-    updateSort();
-    $('#mods').pagination({
-        dataSource: currentMods,
-        showPrevious: false,
-        showNext: false,
-        showPageNumbers: false,
-        callback: function(data, pagination) {
-            $("#mods").html(createMods(data));
-            move(undefined, $("#mods>button").get(0));
-            pageCount = Math.ceil(pagination["totalNumber"] / pagination["pageSize"]);
-        },
-        afterPaging: function(activePage) {
-            $("#pageInfo").html(`${activePage} of ${pageCount}`);
+function updateConflictNote(id) {
+    if (!(id in conflicts)) {
+        $("#conflictNote").html("");
+        return;
+    }
+
+    var conflict = conflicts[id];
+    $("#conflictNote").html(`Conflicts with ${conflict["with"].join(", ")} on ${conflict["paths"].length} file${conflict["paths"].length != 1 ? 's' : ''} (&#xe002 for details)`);
+}
+
+function disableMod(id) {
+    if (id in mods) {
+        mods[id]["is_disabled"] = true;
+        $(`#btn-mods-${id} .img-check`).addClass("hidden");
+    }
+    enabledCount -= 1;
+
+    sendMessage({
+        "ToggleMod": {
+            "id": id,
+            "state": false
+        }
+    });
+
+    // Refresh the details, so that the conflicts that were resolved go away
+    sendMessage({
+        "GetModDetails": {
+            "id": detailsId
         }
     });
 }
 
+var detailsId = 0;
+
+function showDetails(details) {
+    detailsId = details["id"];
+    var mod = mods[details["id"]];
+    $("#detailsName").html(mod != undefined ? mod["display_name"] : "");
+    $("#detailsSummary").html(`${details["file_count"]} file${details["file_count"] != 1 ? 's' : ''}, ${sizeToFormattedBytes(details["total_size"])}`);
+
+    var kinds = { "Replace": "replaces", "Add": "adds", "Unknown": "" };
+
+    $("#detailsConflicts").html(details["conflicts"].length == 0 ? "None" : details["conflicts"].map(conflict =>
+        `<div class="conflict">${conflict["path"]}: ${conflict["mods"].join(", ")}</div>`).join(""));
+    $("#detailsFiles").html(details["files"].length == 0 ? "None" : details["files"].map(file =>
+        `<div>${file["path"]} (${sizeToFormattedBytes(file["size"])}) ${kinds[file["kind"]]}</div>`).join(""));
+    $("#detailsResolve").html(details["conflicting_mods"].map(other =>
+        `<button class="submenu-btn" onclick="disableMod(${other["id"]});">Disable ${other["name"]}</button>`).join("") +
+        (details["conflicting_mods"].length == 0 || mod == undefined || mod["is_disabled"] ? "" :
+            `<button class="submenu-btn" onclick="disableMod(${details["id"]});">Disable this mod</button>`));
+    $("#detailsPatches").html(details["patches"].length == 0 ? "None" : details["patches"].map(patch => `<div>${patch}</div>`).join(""));
+
+    $("#inner-details").scrollTop(0);
+    $("#details").css("display", "flex");
+    $("#detailsResolve>button").first().focus();
+    currentState = DETAILS_MENU;
+}
+
+function hideDetails() {
+    $("#details").css("display", "none");
+    currentState = MOD_MENU;
+}
+
+function showModMenu() {
+    $("#submenu").css("display", "none");
+    document.querySelector('meta[name="focus-ring-visibility"]').setAttribute("content", "hidden");
+    requestPage(1, "first");
+    currentState = MOD_MENU;
+}
+
 function setAllState(state, src) {
-    for (var i = 0; i < mods.length; i++) {
-        mods[i]["is_disabled"] = !state;
-    }
-    refreshCurrentMods();
-    src != undefined || src != null ? src.focus() : false;
-    window.nx.sendMessage(JSON.stringify({
+    sendMessage({
         "ChangeAll": {
             "state": state
         }
-    }));
+    });
+    src != undefined || src != null ? src.focus() : false;
 }
 
 function setCurrentModsState(state, src) {
-    updateCurrentModsWCategories();
-    for (var i = 0; i < currentMods.length; i++) {
-        mods[currentMods[i]]["is_disabled"] = !state;
-    }
-    refreshCurrentMods();
-    src != undefined || src != null ? src.focus() : false;
-    if (currentMods.length <= 0) { return; }
-    window.nx.sendMessage(JSON.stringify({
-        "ChangeIndexes": {
+    sendMessage({
+        "ChangeMatching": {
             "state": state,
-            "indexes": currentMods
+            "query": currentQuery()
         }
-    }));
+    });
+    src != undefined || src != null ? src.focus() : false;
 }
 
 function exit() {
-    window.nx.sendMessage(JSON.stringify("Closure"));
+    sendMessage("Closure");
     window.location.href = "http://localhost/quit";
 }
 
-function updateSort() {
-    var descending = document.getElementById('desc').checked;
-    var sortType = document.getElementById('sortOptions').value;
+function handleMessage(info) {
+    if ("mod_size" in info) {
+        modSize = info["mod_size"];
+    }
 
-    if (sortType == "alphabetical") {
-        currentMods = JSON.parse(JSON.stringify(currentMods)).sort((a, b) => {
-            if (mods[a]["display_name"] < mods[b]["display_name"]) { return -1; }
-            if (mods[a]["display_name"] > mods[b]["display_name"]) { return 1; }
-            return 0;
-        });
-    } else if (sortType == "enabled") {
-        currentMods = JSON.parse(JSON.stringify(currentMods)).sort((a, b) => {
-            if (!mods[a]["is_disabled"] != !mods[b]["is_disabled"]) {
-                return mods[b]["is_disabled"] ? -1 : 1;
-            } else {
-                if (mods[a]["display_name"] < mods[b]["display_name"]) { return -1; }
-                if (mods[a]["display_name"] > mods[b]["display_name"]) { return 1; }
+    if ("enabled_count" in info) {
+        enabledCount = info["enabled_count"];
+        updateStats();
+    }
+
+    if ("page" in info) {
+        renderPage(info["page"]);
+    }
+
+    if ("conflicts" in info) {
+        updateConflicts(info["conflicts"]);
+    }
+
+    if ("details" in info) {
+        showDetails(info["details"]);
+    }
+}
+
+// Stands in for the Rust side when the page is opened outside of the console
+var fakeMods = [];
+
+function fakeServer(message) {
+    if (typeof message != "object") { return; }
+
+    if ("ToggleMod" in message) {
+        fakeMods[message["ToggleMod"]["id"]]["is_disabled"] = !message["ToggleMod"]["state"];
+    } else if ("ChangeAll" in message) {
+        fakeMods.forEach(mod => mod["is_disabled"] = !message["ChangeAll"]["state"]);
+        handleMessage({ "enabled_count": fakeMods.filter(mod => !mod["is_disabled"]).length });
+    } else if ("ChangeMatching" in message) {
+        fakeQuery(message["ChangeMatching"]["query"]).forEach(mod => mod["is_disabled"] = !message["ChangeMatching"]["state"]);
+        handleMessage({ "enabled_count": fakeMods.filter(mod => !mod["is_disabled"]).length });
+    } else if ("GetModDetails" in message) {
+        handleMessage({
+            "details": {
+                "id": message["GetModDetails"]["id"],
+                "file_count": 1,
+                "total_size": 1024,
+                "files": [{ "path": "fighter/mario/model/body/c00/model.numdl", "size": 1024, "kind": "Replace" }],
+                "patches": [],
+                "conflicts": [],
+                "conflicting_mods": []
             }
-            return 0;
         });
-    } else if (sortType == "disabled") {
-        currentMods = JSON.parse(JSON.stringify(currentMods)).sort((a, b) => {
-            if (!mods[a]["is_disabled"] != !mods[b]["is_disabled"]) {
-                return mods[b]["is_disabled"] ? 1 : -1;
-            } else {
-                if (mods[a]["display_name"] < mods[b]["display_name"]) { return -1; }
-                if (mods[a]["display_name"] > mods[b]["display_name"]) { return 1; }
+    } else if ("QueryPage" in message) {
+        var request = message["QueryPage"];
+        var matching = fakeQuery(request["query"]);
+        var count = Math.max(1, Math.ceil(matching.length / request["page_size"]));
+        var page = Math.min(Math.max(request["page"], 1), count);
+
+        handleMessage({
+            "page": {
+                "entries": matching.slice((page - 1) * request["page_size"], page * request["page_size"]),
+                "page": page,
+                "page_count": count,
+                "matching": matching.length,
+                "mod_count": fakeMods.length,
+                "enabled_count": fakeMods.filter(mod => !mod["is_disabled"]).length
             }
-            return 0;
         });
     }
+}
+
+function fakeQuery(query) {
+    var search = query["search"].toLowerCase();
+    var author = query["author"].toLowerCase();
+
+    var matching = fakeMods.filter(mod =>
+        (query["categories"].length == 0 || query["categories"].includes(mod["category"])) &&
+        (search == "" || mod["display_name"].toLowerCase().includes(search) || mod["description"].toLowerCase().includes(search)) &&
+        (author == "" || mod["authors"].toLowerCase().includes(author)));
+
+    matching.sort((a, b) => {
+        if (query["sort"] == "Enabled" && a["is_disabled"] != b["is_disabled"]) {
+            return a["is_disabled"] ? 1 : -1;
+        } else if (query["sort"] == "Disabled" && a["is_disabled"] != b["is_disabled"]) {
+            return a["is_disabled"] ? -1 : 1;
+        }
+        return a["display_name"].localeCompare(b["display_name"]);
+    });
 
-    if (descending) {
-        currentMods.reverse();
+    if (query["descending"]) {
+        matching.reverse();
     }
+
+    return matching;
 }
 
-window.nx.addEventListener("message", (e) => {
-    var info = JSON.parse(e.data);
-    if (!("mod_size" in info))
-        return;
-    
-    modSize = info["mod_size"];
-});
+if (isNx) {
+    window.nx.addEventListener("message", (e) => {
+        handleMessage(JSON.parse(e.data));
+    });
+}
 
 window.addEventListener("DOMContentLoaded", (e) => {
     if (!isNx) {
-        mods = [];
+        fakeMods = [];
         for (var i = 0; i < 9999; i++) {
-            mods.push({
+            fakeMods.push({
                 "id": i,
                 "display_name": `Mod #${i}`,
                 "version": `${i + 3}.${i + 2}.${i}`,
@@ -449,18 +664,18 @@ window.addEventListener("DOMContentLoaded", (e) => {
             });
         }
 
-        currentMods = mods.map(x => x["id"]);
-        refreshCurrentMods();
+        requestPage(1, "first");
     } else {
 
         $.ajax({
             dataType: "json",
             url: "mods.json",
             success: (data) => {
-                mods = data["entries"];
                 $("#workspace").html(data["workspace"]);
-                currentMods = mods.map(x => x["id"]);
-                refreshCurrentMods();
+                modCount = data["mod_count"];
+                requestPage(1, "first");
+                // Only answered once every mod has been scanned
+                sendMessage("GetConflicts");
             }
         });
 
@@ -473,8 +688,6 @@ window.addEventListener("DOMContentLoaded", (e) => {
 
         window.nx.footer.setAssign("B", "", () => {});
         window.nx.footer.setAssign("X", "", () => {});
-        window.nx.sendMessage(JSON.stringify("GetModSize"));
+        sendMessage("GetModSize");
     }
 });
-
-// window.nx.sendMessage(JSON.stringify("GetModSize"));
\ No newline at end of file
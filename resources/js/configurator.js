@@ -47,6 +47,12 @@ function getCurrentActiveContainer() {
         return $("#workspaces");
     } else if ($("#logging").is(":visible")) {
         return $("#logging");
+    } else if ($("#file_logging").is(":visible")) {
+        return $("#file_logging");
+    } else if ($("#region").is(":visible")) {
+        return $("#region");
+    } else if ($("#fallback").is(":visible")) {
+        return $("#fallback");
     }
 }
 
@@ -80,6 +86,28 @@ function submit(cat, type) {
     //location.href = `http://localhost/${result}`;
 }
 
+function editDirectives() {
+    // For example: arcropolis::fs=trace,arcropolis::replacement=warn
+    var directives = prompt("Per-module logging levels, separated by commas (leave empty to clear)", "");
+
+    if (directives === null) {
+        return;
+    }
+
+    submit(`log_directives`, directives.trim());
+}
+
+function editUpdatePin() {
+    // For example: 3.6.2
+    var version = prompt("Only ever update to this version of ARCropolis (leave empty to follow the latest release)", "");
+
+    if (version === null) {
+        return;
+    }
+
+    submit(`update_pin`, version.trim());
+}
+
 function checkGamepad(index, gamepad) {
     //#region UI Input Check
 
@@ -0,0 +1,302 @@
+var workspace = "";
+var mods = {};
+var paths = [];
+var AButtonHeld = false;
+var BButtonHeld = false;
+var XButtonHeld = false;
+
+window.addEventListener("DOMContentLoaded", (e) => {
+    if (!isNx) {
+        handleInformation({
+            "workspace": "Default",
+            "mods": [{ "id": 0, "name": "Mario Recolor" }, { "id": 1, "name": "Mario Model Swap" }, { "id": 2, "name": "Stage Overhaul" }],
+            "paths": [
+                { "path": "fighter/mario/model/body/c00/model.numdl", "mods": [0, 1], "winner": null },
+                { "path": "fighter/mario/model/body/c00/def_mario_001_col.nutexb", "mods": [0, 1], "winner": 1 },
+                { "path": "stage/battlefield/normal/param/stprm.nuanmb", "mods": [1, 2], "winner": null }
+            ]
+        });
+    } else {
+        $.ajax({
+            dataType: "json",
+            url: "conflicts.json",
+            success: (data) => {
+                handleInformation(data);
+            }
+        });
+
+        window.nx.addEventListener("message", (e) => {
+            handleMessage(JSON.parse(e.data));
+        });
+
+        window.nx.footer.setAssign("A", "", () => {});
+        window.nx.footer.setAssign("B", "", () => {});
+        window.nx.footer.setAssign("X", "", () => {});
+        window.nx.footer.setAssign("Y", "", () => {});
+    }
+
+    // Listen to the keydown event and prevent the default
+    window.addEventListener('keydown', function(e) {
+        e.preventDefault();
+    });
+});
+
+function sendMessage(message) {
+    if (isNx) {
+        window.nx.sendMessage(JSON.stringify(message));
+    } else {
+        fakeServer(message);
+    }
+}
+
+function exit() {
+    sendMessage("Closure");
+    window.location.href = "http://localhost/quit";
+}
+
+function handleInformation(info) {
+    workspace = info["workspace"];
+    info["mods"].forEach(mod => mods[mod["id"]] = mod["name"]);
+    paths = info["paths"];
+
+    $("#workspace").html(workspace);
+    setupConflicts();
+}
+
+function handleMessage(info) {
+    if ("paths" in info) {
+        paths = info["paths"];
+        setupConflicts();
+    }
+}
+
+function fakeServer(message) {
+    if (typeof message != "object") { return; }
+
+    if ("SetWinner" in message) {
+        var request = message["SetWinner"];
+        paths.filter(path => path["path"] == request["path"]).forEach(path => path["winner"] = request["id"]);
+    } else if ("SetPairWinner" in message) {
+        var request = message["SetPairWinner"];
+        pairPaths(request["mods"][0], request["mods"][1]).forEach(path => {
+            if (request["id"] != null || request["mods"].includes(path["winner"])) {
+                path["winner"] = request["id"];
+            }
+        });
+    }
+
+    handleMessage({ "paths": paths });
+}
+
+function pairPaths(first, second) {
+    return paths.filter(path => path["mods"].includes(first) && path["mods"].includes(second));
+}
+
+// Every pair of mods providing at least one same path
+function getPairs() {
+    var pairs = {};
+
+    paths.forEach(path => {
+        var owners = path["mods"].slice().sort((a, b) => a - b);
+
+        for (var i = 0; i < owners.length; i++) {
+            for (var j = i + 1; j < owners.length; j++) {
+                var key = `${owners[i]}-${owners[j]}`;
+                if (!(key in pairs)) {
+                    pairs[key] = { "mods": [owners[i], owners[j]], "paths": [] };
+                }
+                pairs[key]["paths"].push(path);
+            }
+        }
+    });
+
+    return Object.values(pairs);
+}
+
+// The mod picked for every path of the pair, null if none was picked and undefined if the paths disagree
+function pairWinner(pair) {
+    var winners = pair["paths"].map(path => pair["mods"].includes(path["winner"]) ? path["winner"] : null);
+    return winners.every(winner => winner == winners[0]) ? winners[0] : undefined;
+}
+
+function createButton(key, title, subtitle, resolved, onclick) {
+    return `<button onclick="${onclick}" data-key="${key}" class="flex-item ${resolved ? "resolved" : ""}">
+    <div class="icon-background"></div>
+    <div class="item-container">
+        <h2>${title}</h2>
+        <p>${subtitle}</p>
+    </div>
+</button>`;
+}
+
+function setupConflicts() {
+    var focused = $(".is-focused").attr("data-key");
+    var htmlText = `<h2 class="section-title">Mod pairs</h2>`;
+
+    getPairs().forEach(pair => {
+        var first = pair["mods"][0];
+        var second = pair["mods"][1];
+        var winner = pairWinner(pair);
+        var subtitle = winner == undefined ? "Mixed winners" : winner == null ? "No winner picked" : `${mods[winner]} wins`;
+
+        htmlText += createButton(
+            `pair-${first}-${second}`,
+            `${mods[first]} / ${mods[second]}`,
+            `${pair["paths"].length} file(s) &nbsp;-&nbsp; ${subtitle}`,
+            winner != undefined && winner != null,
+            `cyclePairWinner(${first}, ${second})`
+        );
+    });
+
+    htmlText += `<h2 class="section-title">Files</h2>`;
+
+    paths.forEach((path, index) => {
+        var owners = path["mods"].map(id => mods[id]).join(", ");
+        var subtitle = path["winner"] == null ? owners : `${mods[path["winner"]]} wins &nbsp;-&nbsp; ${owners}`;
+
+        htmlText += createButton(`path-${index}`, path["path"], subtitle, path["winner"] != null, `cycleWinner(${index})`);
+    });
+
+    document.getElementById("conflictsContainer").innerHTML = htmlText;
+
+    var buttons = document.querySelectorAll('button');
+
+    [].forEach.call(buttons, function(btn) {
+        btn.addEventListener("focus", () => {
+            btn.classList.add("is-focused");
+        });
+
+        btn.addEventListener("focusout", () => {
+            btn.classList.remove("is-focused");
+        });
+    });
+
+    var target = $(`button[data-key='${focused}']`);
+    if (target.length <= 0) {
+        target = $("#conflictsContainer>button").first();
+    }
+    target.get(0).focus();
+}
+
+// Goes from no winner, to the first mod, to the second mod and back to no winner
+function cyclePairWinner(first, second) {
+    var winner = pairWinner({ "mods": [first, second], "paths": pairPaths(first, second) });
+    var next = winner == first ? second : winner == second ? null : first;
+
+    sendMessage({
+        "SetPairWinner": {
+            "mods": [first, second],
+            "id": next
+        }
+    });
+}
+
+// Goes through every mod providing the path, then back to no winner
+function cycleWinner(index) {
+    var path = paths[index];
+    var position = path["mods"].indexOf(path["winner"]);
+    var next = position + 1 < path["mods"].length ? path["mods"][position + 1] : null;
+
+    sendMessage({
+        "SetWinner": {
+            "path": path["path"],
+            "id": next
+        }
+    });
+}
+
+function clearWinner() {
+    var key = $(".is-focused").attr("data-key");
+    if (key == undefined) { return; }
+
+    var parts = key.split("-");
+
+    if (parts[0] == "pair") {
+        sendMessage({
+            "SetPairWinner": {
+                "mods": [parseInt(parts[1]), parseInt(parts[2])],
+                "id": null
+            }
+        });
+    } else {
+        sendMessage({
+            "SetWinner": {
+                "path": paths[parseInt(parts[1])]["path"],
+                "id": null
+            }
+        });
+    }
+}
+
+function checkGamepad(index, gamepad) {
+    //#region UI Input Check
+
+    var axisY = gamepad.axes[1];
+
+    // Check A button
+    if (gamepad.buttons[1].pressed) {
+        if (!AButtonHeld) {
+            AButtonHeld = true;
+            if ($(".is-focused").length <= 0) {
+                $("button:visible").get(0).focus();
+            } else {
+                $(".is-focused").get(0).click();
+            }
+        }
+    } else {
+        AButtonHeld = false;
+    }
+
+    // Check B Button
+    if (gamepad.buttons[0].pressed) {
+        if (!BButtonHeld) {
+            BButtonHeld = true;
+            exit();
+        }
+    } else {
+        BButtonHeld = false;
+    }
+
+    // Check X button
+    if (gamepad.buttons[3].pressed) {
+        if (!XButtonHeld) {
+            XButtonHeld = true;
+            clearWinner();
+        }
+    } else {
+        XButtonHeld = false;
+    }
+
+    var target = undefined;
+    var offset = undefined;
+
+    // Check if D-pad Up pressed or Y-Axis
+    if (gamepad.buttons[12].pressed || axisY < -0.7) {
+        // Skip over the section titles
+        target = $(".is-focused").prevAll("button:visible").first();
+
+        // If that doesn't exist, then dip
+        if (target.length <= 0) {
+            return;
+        }
+
+        offset = $("#conflicts .scrollbar").scrollTop() + target.position().top - 50;
+    }
+    // Check if D-pad Down pressed or Y Axis > 0.7
+    else if (gamepad.buttons[13].pressed || axisY > 0.7) {
+        target = $(".is-focused").nextAll("button:visible").first();
+
+        // If there is none after that, then just return
+        if (target.length <= 0) {
+            return;
+        }
+
+        offset = $("#conflicts .scrollbar").scrollTop() + (target.height() * 2);
+    };
+
+    if (target != undefined) {
+        scroll(target, offset, $("#conflicts .scrollbar"));
+    }
+
+    //#endregion
+}
@@ -26,6 +26,17 @@ window.addEventListener("DOMContentLoaded", (e) => {
     });
 });
 
+function reloadFiles() {
+    // Leave the prompt empty to reload every modded file
+    var target = prompt("Path or hash of the file to reload (leave empty for all files)", "");
+
+    if (target === null) {
+        return;
+    }
+
+    location.href = `http://localhost/reload/${target.trim()}`;
+}
+
 function checkGamepad(index, gamepad) {
     //#region UI Input Check
 
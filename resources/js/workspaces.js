@@ -4,6 +4,7 @@ const WORKSPACE_CONTROL = "&#xe000 Select Option";
 var workspaces = [];
 var selected_workspace = 0;
 var active_workspace = "";
+var overrides = {};
 var AButtonHeld = false;
 var BButtonHeld = false;
 var XButtonHeld = false;
@@ -24,10 +25,15 @@ window.addEventListener("DOMContentLoaded", (e) => {
             success: (data) => {
                 workspaces = data["workspaces"];
                 active_workspace = data["active_workspace"];
+                overrides = data["overrides"];
                 setupWorkspaces();
             }
         });
 
+        window.nx.addEventListener("message", (e) => {
+            handleMessage(JSON.parse(e.data));
+        });
+
         // window.nx.sendMessage(JSON.stringify({
         //     "WriteItDown": {
         //         "text": JSON.stringify(Object.getOwnPropertyNames(window.nx).filter(function(p) {
@@ -51,6 +57,16 @@ window.addEventListener("DOMContentLoaded", (e) => {
     });
 });
 
+function handleMessage(info) {
+    if ("overrides" in info) {
+        overrides[info["overrides"]["name"]] = info["overrides"]["text"];
+    }
+
+    if ("error" in info) {
+        alert(info["error"]);
+    }
+}
+
 function goBack() {
     if (getCurrentActiveContainer().attr('id') == "workspaceOption") {
         changeDivFromTo('workspaceOption', 'workspaces', selected_workspace);
@@ -333,6 +349,11 @@ function renameWorkspace() {
 
     workspaces[selected_workspace] = targetName;
 
+    if (sourceName in overrides) {
+        overrides[targetName] = overrides[sourceName];
+        delete overrides[sourceName];
+    }
+
     $("#workspace").html(workspaces[selected_workspace]);
 
     if (isNx) {
@@ -363,6 +384,10 @@ function duplicateWorkspace() {
 
     workspaces.push(targetName);
 
+    if (sourceName in overrides) {
+        overrides[targetName] = overrides[sourceName];
+    }
+
     if (isNx) {
         window.nx.sendMessage(JSON.stringify({
             "Duplicate": {
@@ -425,4 +450,23 @@ function editWorkspace() {
         }));
         window.location.href = "http://localhost/quit";
     }
-}
\ No newline at end of file
+}
+
+function editOverrides() {
+    var name = workspaces[selected_workspace];
+    var current = name in overrides ? overrides[name] : "";
+
+    var res = prompt("Settings to override, as comma separated TOML values (e.g. debug = true, region_override = \"eu_fr\")", current);
+    if (res == null || res == undefined) { return; }
+
+    if (isNx) {
+        window.nx.sendMessage(JSON.stringify({
+            "SetOverrides": {
+                "name": name,
+                "overrides": res
+            }
+        }));
+    } else {
+        overrides[name] = res;
+    }
+}
@@ -1,11 +1,15 @@
 pub mod callback;
+pub mod config;
 pub mod event;
 pub mod file;
+pub mod redirect;
 pub mod utils;
 
 pub use callback::*;
+pub use config::*;
 pub use event::*;
 pub use file::*;
+pub use redirect::*;
 pub use utils::*;
 
 #[repr(C)]
@@ -22,7 +26,7 @@ pub struct ApiVersion {
 /// Do your changes only add new APIs in a backwards compatible way: Minor bump
 ///
 /// Are your changes only internal? No version bump
-static API_VERSION: ApiVersion = ApiVersion { major: 1, minor: 8 };
+static API_VERSION: ApiVersion = ApiVersion { major: 1, minor: 11 };
 
 #[no_mangle]
 pub extern "C" fn arcrop_api_version() -> &'static ApiVersion {
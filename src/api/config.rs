@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use arc_config::Config as ModConfig;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use skyline::libc::c_char;
+
+pub static PENDING_CONFIGS: Lazy<Mutex<Vec<ModConfig>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set once the mods have been processed, after which submitted configs would no longer have any effect
+static CONFIGS_PROCESSED: AtomicBool = AtomicBool::new(false);
+
+/// The merged config serialized once it is final, so that plugins can read it without going through the filesystem lock
+static MERGED_CONFIG_JSON: OnceCell<String> = OnceCell::new();
+
+/// Merges every config submitted by plugins so far into the provided config.
+/// Once `finished` is set, any further submission is refused and the merged config is made available to plugins.
+pub fn merge_pending_configs(config: &mut ModConfig, finished: bool) {
+    let mut pending = PENDING_CONFIGS.lock();
+
+    for cfg in pending.drain(..) {
+        config.merge(cfg);
+    }
+
+    if finished {
+        match serde_json::to_string(config) {
+            Ok(json) => {
+                let _ = MERGED_CONFIG_JSON.set(json);
+            },
+            Err(e) => error!("Failed to serialize the merged config. Reason: {:?}", e),
+        }
+
+        CONFIGS_PROCESSED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn arcrop_submit_config_json(json: *const c_char) -> bool {
+    let json = unsafe { skyline::from_c_str(json) };
+
+    debug!("arcrop_submit_config_json -> Received config with length {:#x}", json.len());
+
+    let cfg = match ModConfig::from_json(&json) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            warn!("arcrop_submit_config_json -> Failed to parse the submitted config. Reason: {:?}", e);
+            return false;
+        },
+    };
+
+    let mut pending = PENDING_CONFIGS.lock();
+
+    if CONFIGS_PROCESSED.load(Ordering::SeqCst) {
+        warn!("arcrop_submit_config_json -> The mods have already been processed, the submitted config will be ignored. Configs must be submitted while the game is booting.");
+        false
+    } else {
+        pending.push(cfg);
+        drop(pending);
+
+        // Only configs that are going to be applied get to name hashes
+        crate::hashes::learn_from_json(&json);
+        true
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn arcrop_get_merged_config_json(out_buffer: *mut u8, buf_length: usize, out_size: &mut usize) -> bool {
+    debug!("arcrop_get_merged_config_json -> Buffer len: {:#x}", buf_length);
+
+    let json = match MERGED_CONFIG_JSON.get() {
+        Some(json) => json,
+        None => {
+            *out_size = 0;
+            return false;
+        },
+    };
+
+    // Always report the required size so that callers can retry with a big enough buffer
+    *out_size = json.len();
+
+    if buf_length < json.len() {
+        debug!("arcrop_get_merged_config_json -> Buffer is too small, {:#x} bytes are required", json.len());
+        return false;
+    }
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(out_buffer, buf_length) };
+    buffer[..json.len()].copy_from_slice(json.as_bytes());
+
+    true
+}
@@ -75,6 +75,19 @@ pub extern "C" fn arcrop_is_file_loaded(hash: Hash40) -> bool {
     }
 }
 
+#[no_mangle]
+pub extern "C" fn arcrop_invalidate_file(hash: Hash40) -> bool {
+    debug!("arcrop_invalidate_file -> Received hash {} ({:#x})", hashes::find(hash).green(), hash.0);
+
+    match crate::replacement::reload::invalidate(hash) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("arcrop_invalidate_file -> Failed to invalidate '{}' ({:#x}): {}", hashes::find(hash), hash.0, e);
+            false
+        },
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn arcrop_is_mod_enabled(hash: Hash40) -> bool {
     debug!("arcrop_is_mod_enabled -> Received hash {} ({:#x})", hashes::find(hash).green(), hash.0);
@@ -83,7 +96,7 @@ pub extern "C" fn arcrop_is_mod_enabled(hash: Hash40) -> bool {
 
     let is_emulator = unsafe { skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as u64 } == 0x8004000;
 
-    let preset: HashSet<Hash40> = if storage.get_flag("legacy_discovery") || is_emulator {
+    let preset: HashSet<Hash40> = if config::legacy_discovery() || is_emulator {
         WalkDir::new(crate::config::umm_path())
             .max_depth(1)
             .into_iter()
@@ -112,7 +125,7 @@ pub extern "C" fn arcrop_is_mod_enabled(hash: Hash40) -> bool {
             })
             .collect()
     } else {
-        let workspace_name: String = storage.get_field("workspace").unwrap_or_else(|_| "Default".to_string());
+        let workspace_name: String = config::active_workspace();
         let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
         let preset_name = &workspace_list[&workspace_name];
         storage.get_field_json(preset_name).unwrap_or_default()
@@ -0,0 +1,92 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use once_cell::sync::Lazy;
+use owo_colors::OwoColorize;
+use parking_lot::RwLock;
+use skyline::libc::c_char;
+use smash_arc::Hash40;
+
+use crate::{fs::GlobalFilesystem, hashes};
+
+/// Called right before a file is inflated. Returning true redirects the request: if `out_path` was filled with a
+/// non-empty string the file at that path is loaded, otherwise the file at `out_hash` is loaded.
+pub type RedirectCallbackFn = extern "C" fn(hash: u64, out_hash: &mut u64, out_path: *mut c_char, path_len: usize) -> bool;
+
+const REDIRECT_PATH_LEN: usize = 0x200;
+
+pub enum Redirect {
+    Hash(Hash40),
+    Path(PathBuf),
+}
+
+pub struct RedirectEntry {
+    pub max_size: usize,
+    callbacks: Vec<RedirectCallbackFn>,
+}
+
+pub static REDIRECT_CALLBACKS: Lazy<RwLock<HashMap<Hash40, RedirectEntry>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Asks every redirect callback registered for a hash, most recent first, where the file should be loaded from
+pub fn resolve(hash: Hash40) -> Option<Redirect> {
+    // The callbacks are copied out so that they can register redirects themselves without deadlocking
+    let callbacks = REDIRECT_CALLBACKS.read().get(&hash)?.callbacks.clone();
+
+    for callback in callbacks.iter().rev() {
+        let mut out_hash = hash.0;
+        let mut out_path = [0u8; REDIRECT_PATH_LEN];
+
+        if !callback(hash.0, &mut out_hash, out_path.as_mut_ptr() as *mut c_char, out_path.len()) {
+            continue;
+        }
+
+        let path_len = out_path.iter().position(|x| *x == 0).unwrap_or(out_path.len());
+
+        let redirect = match std::str::from_utf8(&out_path[..path_len]) {
+            Ok("") => Redirect::Hash(Hash40(out_hash)),
+            Ok(path) => Redirect::Path(PathBuf::from(path)),
+            Err(_) => {
+                warn!(
+                    "A redirect callback for '{}' ({:#x}) provided a path that is not valid UTF-8.",
+                    hashes::find(hash),
+                    hash.0
+                );
+                continue;
+            },
+        };
+
+        return Some(redirect);
+    }
+
+    None
+}
+
+#[no_mangle]
+pub extern "C" fn arcrop_register_redirect(hash: Hash40, max_size: usize, cb: RedirectCallbackFn) {
+    debug!(
+        "arcrop_register_redirect -> Hash received: {} ({:#x}), Max size: {:#x}",
+        hashes::find(hash).green(),
+        hash.0,
+        max_size
+    );
+
+    let mut redirects = REDIRECT_CALLBACKS.write();
+
+    let max_size = match redirects.get_mut(&hash) {
+        Some(entry) => {
+            entry.max_size = entry.max_size.max(max_size);
+            entry.callbacks.push(cb);
+            entry.max_size
+        },
+        None => {
+            redirects.insert(hash, RedirectEntry { max_size, callbacks: vec![cb] });
+            max_size
+        },
+    };
+
+    drop(redirects);
+
+    // Before the filesystem is ready, the sizes are picked up along with the rest of the API calls
+    if GlobalFilesystem::is_init() {
+        crate::GLOBAL_FILESYSTEM.write().reserve_size(hash, max_size);
+    }
+}
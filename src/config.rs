@@ -1,11 +1,13 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::PathBuf,
+    str::FromStr,
     sync::Mutex,
 };
 
+use log::{Level, LevelFilter};
 use once_cell::sync::Lazy;
-use parking_lot::RwLock;
+use parking_lot::{RwLock, RwLockReadGuard};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use skyline::nn;
@@ -76,13 +78,7 @@ pub static GLOBAL_CONFIG: Lazy<Mutex<StorageHolder<ArcStorage>>> = Lazy::new(||
 
             // Check if the configuration is from a previous version
             if curr_version > config_version {
-                // TODO: Code to perform changes for each version
-                if Version::new(3, 2, 0) > config_version {
-                    let mut default_workspace = HashMap::<&str, &str>::new();
-                    default_workspace.insert("Default", "presets");
-                    storage.set_field_json("workspace_list", &default_workspace).unwrap();
-                    storage.set_field("workspace", "Default").unwrap();
-                }
+                run_migrations(&mut storage, &config_version, &curr_version);
                 // Update the version in the config
                 storage.set_field("version", arcropolis_version()).unwrap();
             }
@@ -144,20 +140,607 @@ pub static GLOBAL_CONFIG: Lazy<Mutex<StorageHolder<ArcStorage>>> = Lazy::new(||
         },
     }
 
+    *SETTINGS.write() = effective_settings(&storage);
+
     Mutex::new(storage)
 });
 
+type Migration = fn(&mut StorageHolder<ArcStorage>);
+
+/// Every change made to the layout of the configuration, in the order they have to be applied.
+/// A migration runs if the configuration was last written by a version older than the one it is listed under.
+static MIGRATIONS: &[(Version, &str, Migration)] = &[(Version::new(3, 2, 0), "Introduce workspaces", migrate_workspaces)];
+
+fn run_migrations(storage: &mut StorageHolder<ArcStorage>, from: &Version, to: &Version) {
+    report(Level::Info, format!("Migrating configuration from {} to {}.", from, to));
+
+    for (version, description, migration) in MIGRATIONS.iter() {
+        if version > from && version <= to {
+            report(Level::Info, format!("Applying configuration migration for {}: {}", version, description));
+            migration(storage);
+        }
+    }
+}
+
+/// [3.2.0] Presets are now stored per workspace, with the existing presets becoming the default workspace
+fn migrate_workspaces(storage: &mut StorageHolder<ArcStorage>) {
+    let mut default_workspace = HashMap::<&str, &str>::new();
+    default_workspace.insert("Default", "presets");
+    storage.set_field_json("workspace_list", &default_workspace).unwrap();
+    storage.set_field("workspace", "Default").unwrap();
+}
+
+/// Messages produced while loading the configuration, which happens before the logger is ready
+static PENDING_MESSAGES: Lazy<Mutex<Vec<(Level, String)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn report(level: Level, message: String) {
+    if log::max_level() == LevelFilter::Off {
+        PENDING_MESSAGES.lock().unwrap().push((level, message));
+    } else {
+        log!(level, "{}", message);
+    }
+}
+
+/// Logs everything that happened while loading the configuration. Meant to be called once the logger is initialized.
+pub fn log_pending_messages() {
+    for (level, message) in PENDING_MESSAGES.lock().unwrap().drain(..) {
+        log!(level, "{}", message);
+    }
+}
+
+/// Every setting ARCropolis reads from the configuration storage, validated and with defaults applied
+#[derive(Debug, Clone, Serialize)]
+pub struct Settings {
+    /// Region used instead of the one configured in the game, if set
+    pub region_override: Option<String>,
+    /// Regions to take regional mod files from, in order, when a file has no variant for the active region
+    pub region_fallbacks: Vec<String>,
+    pub logging_level: String,
+    /// Level of the log file, if it should differ from the one of the console
+    pub file_logging_level: Option<String>,
+    /// Per-module levels, such as `arcropolis::fs=trace,arcropolis::replacement=warn`
+    pub log_directives: String,
+    pub log_to_file: bool,
+    /// How many boots worth of logs are kept on the SD card, 0 keeps every log
+    pub log_retention_count: usize,
+    /// How many days logs are kept on the SD card for, 0 keeps logs regardless of their age
+    pub log_retention_days: u64,
+    /// Size in megabytes after which the logger moves on to a new file, 0 for no limit
+    pub log_max_size_mb: u64,
+    /// Whether the logs of previous boots are compressed with gzip
+    pub compress_old_logs: bool,
+    pub extra_paths: Vec<String>,
+    pub auto_update: bool,
+    pub beta_updates: bool,
+    /// Version the updater is pinned to, if set. No other version is ever offered.
+    pub update_pin: Option<String>,
+    /// Versions the user chose to never be offered again
+    pub skipped_updates: Vec<String>,
+    pub debug: bool,
+    pub legacy_discovery: bool,
+    pub hot_reload: bool,
+    pub trace_file_loads: bool,
+    pub workspace: String,
+    /// Whether the plugin.nro files found in mods are chainloaded at all
+    pub chainload_plugins: bool,
+    /// Names of the mod folders whose plugin must not be chainloaded
+    pub plugin_blocklist: Vec<String>,
+    /// Whether the slot of costumes past the 8th is rewritten when playing online
+    pub online_slot_spoof: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            region_override: None,
+            region_fallbacks: Vec::new(),
+            logging_level: default_logger_level(),
+            file_logging_level: None,
+            log_directives: String::new(),
+            log_to_file: false,
+            log_retention_count: 10,
+            log_retention_days: 0,
+            log_max_size_mb: 32,
+            compress_old_logs: false,
+            extra_paths: Vec::new(),
+            auto_update: true,
+            beta_updates: false,
+            update_pin: None,
+            skipped_updates: Vec::new(),
+            debug: false,
+            legacy_discovery: false,
+            hot_reload: false,
+            trace_file_loads: false,
+            workspace: String::from("Default"),
+            chainload_plugins: true,
+            plugin_blocklist: Vec::new(),
+            online_slot_spoof: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the storage. Values that are missing or invalid are replaced by their default.
+    pub fn load<CS: ConfigStorage>(storage: &StorageHolder<CS>) -> Self {
+        let default = Self::default();
+
+        let settings = Self {
+            region_override: storage.get_field("region_override").ok(),
+            region_fallbacks: storage.get_field_json("region_fallbacks").unwrap_or(default.region_fallbacks),
+            logging_level: storage.get_field("logging_level").unwrap_or(default.logging_level),
+            file_logging_level: storage.get_field("file_logging_level").ok(),
+            log_directives: storage.get_field("log_directives").unwrap_or(default.log_directives),
+            log_to_file: storage.get_flag("log_to_file"),
+            log_retention_count: storage.get_field("log_retention_count").unwrap_or(default.log_retention_count),
+            log_retention_days: storage.get_field("log_retention_days").unwrap_or(default.log_retention_days),
+            log_max_size_mb: storage.get_field("log_max_size_mb").unwrap_or(default.log_max_size_mb),
+            compress_old_logs: storage.get_flag("compress_old_logs"),
+            extra_paths: storage.get_field_json("extra_paths").unwrap_or(default.extra_paths),
+            auto_update: storage.get_flag("auto_update"),
+            beta_updates: storage.get_flag("beta_updates"),
+            update_pin: storage.get_field("update_pin").ok(),
+            skipped_updates: storage.get_field_json("skipped_updates").unwrap_or(default.skipped_updates),
+            debug: storage.get_flag("debug"),
+            legacy_discovery: storage.get_flag("legacy_discovery"),
+            hot_reload: storage.get_flag("hot_reload"),
+            trace_file_loads: storage.get_flag("trace_file_loads"),
+            workspace: storage.get_field("workspace").unwrap_or(default.workspace),
+            // These can only be changed by workspace overrides or settings.toml
+            ..default
+        };
+
+        let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
+
+        settings.validated(&workspace_list)
+    }
+
+    /// Replaces every invalid value by its default, reporting what was changed
+    fn validated(mut self, workspace_list: &HashMap<String, String>) -> Self {
+        let default = Self::default();
+
+        if let Some(region) = self.region_override.take() {
+            if crate::REGIONS.contains(&region.as_str()) {
+                self.region_override = Some(region);
+            } else if region != "auto" {
+                report(
+                    Level::Warn,
+                    format!("'{}' is not a valid region override, using the region configured in the game instead.", region),
+                );
+            }
+        }
+
+        self.region_fallbacks.retain(|region| {
+            let valid = crate::REGIONS.contains(&region.as_str());
+            if !valid {
+                report(Level::Warn, format!("'{}' is not a valid fallback region, ignoring it.", region));
+            }
+            valid
+        });
+
+        if LevelFilter::from_str(&self.logging_level).is_err() {
+            report(
+                Level::Warn,
+                format!("'{}' is not a valid logging level, falling back to '{}'.", self.logging_level, default.logging_level),
+            );
+            self.logging_level = default.logging_level;
+        }
+
+        if let Some(level) = self.file_logging_level.take() {
+            if LevelFilter::from_str(&level).is_ok() {
+                self.file_logging_level = Some(level);
+            } else if level != "Same" {
+                report(
+                    Level::Warn,
+                    format!("'{}' is not a valid file logging level, using the console logging level instead.", level),
+                );
+            }
+        }
+
+        if let Err(e) = crate::logging::parse_directives(&self.log_directives) {
+            report(Level::Warn, format!("Ignoring the log directives '{}'. Reason: {}", self.log_directives, e));
+            self.log_directives = default.log_directives;
+        }
+
+        if let Some(version) = self.update_pin.take() {
+            if semver::Version::parse(version.trim_start_matches('v')).is_ok() {
+                self.update_pin = Some(version.trim_start_matches('v').to_string());
+            } else if version != "none" {
+                report(Level::Warn, format!("'{}' is not a valid version to pin the updater to, ignoring it.", version));
+            }
+        }
+
+        if !workspace_list.contains_key(&self.workspace) {
+            report(
+                Level::Warn,
+                format!("Workspace '{}' does not exist, falling back to '{}'.", self.workspace, default.workspace),
+            );
+            self.workspace = default.workspace;
+        }
+
+        self
+    }
+
+    /// Writes every setting to the storage. The storage still has to be flushed afterwards.
+    pub fn save<CS: ConfigStorage>(&self, storage: &mut StorageHolder<CS>) -> Result<(), ConfigError> {
+        storage.set_field("region_override", self.region_override.as_deref().unwrap_or("auto"))?;
+        storage.set_field_json("region_fallbacks", &self.region_fallbacks)?;
+        storage.set_field("logging_level", &self.logging_level)?;
+        storage.set_field("file_logging_level", self.file_logging_level.as_deref().unwrap_or("Same"))?;
+        storage.set_field("log_directives", &self.log_directives)?;
+        storage.set_flag("log_to_file", self.log_to_file)?;
+        storage.set_field("log_retention_count", self.log_retention_count)?;
+        storage.set_field("log_retention_days", self.log_retention_days)?;
+        storage.set_field("log_max_size_mb", self.log_max_size_mb)?;
+        storage.set_flag("compress_old_logs", self.compress_old_logs)?;
+        storage.set_field_json("extra_paths", &self.extra_paths)?;
+        storage.set_flag("auto_update", self.auto_update)?;
+        storage.set_flag("beta_updates", self.beta_updates)?;
+        storage.set_field("update_pin", self.update_pin.as_deref().unwrap_or("none"))?;
+        storage.set_field_json("skipped_updates", &self.skipped_updates)?;
+        storage.set_flag("debug", self.debug)?;
+        storage.set_flag("legacy_discovery", self.legacy_discovery)?;
+        storage.set_flag("hot_reload", self.hot_reload)?;
+        storage.set_flag("trace_file_loads", self.trace_file_loads)?;
+        storage.set_field("workspace", &self.workspace)?;
+        Ok(())
+    }
+
+    pub fn logger_level(&self) -> LevelFilter {
+        LevelFilter::from_str(&self.logging_level).unwrap_or(LevelFilter::Warn)
+    }
+
+    pub fn file_logger_level(&self) -> LevelFilter {
+        self.file_logging_level
+            .as_deref()
+            .and_then(|level| LevelFilter::from_str(level).ok())
+            .unwrap_or_else(|| self.logger_level())
+    }
+}
+
+const SETTINGS_OVERRIDE_PATH: &str = "sd:/ultimate/arcropolis/settings.toml";
+const EFFECTIVE_SETTINGS_PATH: &str = "sd:/ultimate/arcropolis/effective_settings.toml";
+
+/// Overwrites the fields of the settings with every value present in the overrides, and records the name of the fields that were overridden
+macro_rules! apply_overrides {
+    ($overrides:expr, $settings:expr, $overridden:expr, [$($field:ident),*]) => {
+        $(
+            if let Some(value) = $overrides.$field {
+                $settings.$field = value;
+                $overridden.push(stringify!($field));
+            }
+        )*
+    };
+}
+
+/// Settings a workspace can override, stored as JSON in the `<preset>_overrides` field next to the preset of the workspace.
+/// They take priority over the settings from the menus, but `settings.toml` still has the final say.
+/// Every field is optional, and they are edited from the workspaces menu as a list of TOML values:
+///
+/// ```toml
+/// region_override = "eu_fr", debug = true, plugin_blocklist = ["some_plugin.nro"]
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceOverrides {
+    pub region_override: Option<String>,
+    pub extra_paths: Option<Vec<String>>,
+    pub logging_level: Option<String>,
+    pub log_directives: Option<String>,
+    pub debug: Option<bool>,
+    pub chainload_plugins: Option<bool>,
+    pub plugin_blocklist: Option<Vec<String>>,
+    pub online_slot_spoof: Option<bool>,
+}
+
+impl WorkspaceOverrides {
+    /// Name of the storage field holding the overrides of the workspace using this preset
+    pub fn field_name(preset_name: &str) -> String {
+        format!("{}_overrides", preset_name)
+    }
+
+    /// Formats the overrides the way the workspaces menu shows them, as comma separated `key = value` pairs
+    pub fn to_inline(&self) -> String {
+        toml::to_string(self)
+            .map(|text| text.lines().filter(|line| !line.is_empty()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default()
+    }
+
+    /// Parses overrides formatted like `to_inline` does
+    pub fn from_inline(text: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct Inline {
+            overrides: WorkspaceOverrides,
+        }
+
+        toml::from_str::<Inline>(&format!("overrides = {{ {} }}", text)).map(|inline| inline.overrides)
+    }
+
+    fn apply(self, settings: &mut Settings) -> Vec<&'static str> {
+        let mut overridden = Vec::new();
+
+        if let Some(region) = self.region_override {
+            settings.region_override = Some(region);
+            overridden.push("region_override");
+        }
+
+        apply_overrides!(
+            self,
+            settings,
+            overridden,
+            [
+                extra_paths,
+                logging_level,
+                log_directives,
+                debug,
+                chainload_plugins,
+                plugin_blocklist,
+                online_slot_spoof
+            ]
+        );
+
+        overridden
+    }
+}
+
+/// The mod chosen to provide each of the files that several mods of a workspace provide, stored next to the preset of the
+/// workspace. Discovery loads the chosen file instead of rejecting the conflict, and every other file of the competing
+/// mods still loads.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConflictResolutions {
+    /// Path of the winning mod folder, by path of the file relative to the mod folder
+    pub winners: BTreeMap<String, String>,
+}
+
+impl ConflictResolutions {
+    /// Name of the storage field holding the resolutions of the workspace using this preset
+    pub fn field_name(preset_name: &str) -> String {
+        format!("{}_resolutions", preset_name)
+    }
+
+    pub fn load<CS: ConfigStorage>(storage: &StorageHolder<CS>, preset_name: &str) -> Self {
+        storage.get_field_json(Self::field_name(preset_name)).unwrap_or_default()
+    }
+
+    /// The resolutions of the active workspace
+    pub fn active() -> Self {
+        let storage = GLOBAL_CONFIG.lock().unwrap();
+        let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
+
+        workspace_list
+            .get(&active_workspace())
+            .map(|preset_name| Self::load(&*storage, preset_name))
+            .unwrap_or_default()
+    }
+}
+
+/// Settings read from `settings.toml` on the SD card, which take priority over the ones set in the menus.
+/// Every field is optional, only the ones present in the file are overridden. Keys that are unknown or hold an invalid value
+/// are reported and skipped, so that the rest of the file still applies.
+#[derive(Debug, Default, Deserialize)]
+struct SettingsOverride {
+    region_override: Option<String>,
+    region_fallbacks: Option<Vec<String>>,
+    logging_level: Option<String>,
+    file_logging_level: Option<String>,
+    log_directives: Option<String>,
+    log_to_file: Option<bool>,
+    log_retention_count: Option<usize>,
+    log_retention_days: Option<u64>,
+    log_max_size_mb: Option<u64>,
+    compress_old_logs: Option<bool>,
+    extra_paths: Option<Vec<String>>,
+    auto_update: Option<bool>,
+    beta_updates: Option<bool>,
+    update_pin: Option<String>,
+    skipped_updates: Option<Vec<String>>,
+    debug: Option<bool>,
+    legacy_discovery: Option<bool>,
+    hot_reload: Option<bool>,
+    trace_file_loads: Option<bool>,
+    workspace: Option<String>,
+    chainload_plugins: Option<bool>,
+    plugin_blocklist: Option<Vec<String>>,
+    online_slot_spoof: Option<bool>,
+    /// Keys this version doesn't know about, like typos or settings from a newer version
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+impl SettingsOverride {
+    fn read() -> Option<Self> {
+        let text = std::fs::read_to_string(SETTINGS_OVERRIDE_PATH).ok()?;
+
+        let table: toml::value::Table = match toml::from_str(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                report(
+                    Level::Error,
+                    format!("Failed to parse '{}', none of its settings will be applied. Reason: {}", SETTINGS_OVERRIDE_PATH, e),
+                );
+                return None;
+            },
+        };
+
+        // Every key is checked on its own, so that one bad value doesn't discard the whole file
+        let valid: toml::value::Table = table
+            .into_iter()
+            .filter(|(key, value)| {
+                let mut single = toml::value::Table::new();
+                single.insert(key.clone(), value.clone());
+
+                match toml::Value::Table(single).try_into::<Self>() {
+                    Ok(_) => true,
+                    Err(e) => {
+                        report(
+                            Level::Warn,
+                            format!("Ignoring '{}' in '{}', its value is invalid. Reason: {}", key, SETTINGS_OVERRIDE_PATH, e),
+                        );
+                        false
+                    },
+                }
+            })
+            .collect();
+
+        let overrides: Self = toml::Value::Table(valid).try_into().ok()?;
+
+        for key in overrides.unknown.keys() {
+            report(
+                Level::Warn,
+                format!("Ignoring '{}' in '{}', it is not a setting this version of ARCropolis knows about.", key, SETTINGS_OVERRIDE_PATH),
+            );
+        }
+
+        Some(overrides)
+    }
+
+    /// Overwrites the settings with every value present in the file, returning the name of the fields that were overridden
+    fn apply(self, settings: &mut Settings) -> Vec<&'static str> {
+        let mut overridden = Vec::new();
+
+        if let Some(region) = self.region_override {
+            settings.region_override = Some(region);
+            overridden.push("region_override");
+        }
+
+        if let Some(level) = self.file_logging_level {
+            settings.file_logging_level = Some(level);
+            overridden.push("file_logging_level");
+        }
+
+        if let Some(version) = self.update_pin {
+            settings.update_pin = Some(version);
+            overridden.push("update_pin");
+        }
+
+        apply_overrides!(
+            self,
+            settings,
+            overridden,
+            [
+                region_fallbacks,
+                logging_level,
+                log_directives,
+                log_to_file,
+                log_retention_count,
+                log_retention_days,
+                log_max_size_mb,
+                compress_old_logs,
+                extra_paths,
+                auto_update,
+                beta_updates,
+                skipped_updates,
+                debug,
+                legacy_discovery,
+                hot_reload,
+                trace_file_loads,
+                workspace,
+                chainload_plugins,
+                plugin_blocklist,
+                online_slot_spoof
+            ]
+        );
+
+        overridden
+    }
+}
+
+/// Loads the settings from the storage and layers the overrides of the active workspace and `settings.toml` over them, in that order.
+/// The result is written to `effective_settings.toml` so users can check what ARCropolis is actually running with.
+fn effective_settings<CS: ConfigStorage>(storage: &StorageHolder<CS>) -> Settings {
+    let mut settings = Settings::load(storage);
+    let file_overrides = SettingsOverride::read();
+    let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
+
+    // settings.toml can pick the workspace, so it has to be known before applying the overrides of the workspace
+    let workspace = file_overrides
+        .as_ref()
+        .and_then(|overrides| overrides.workspace.clone())
+        .unwrap_or_else(|| settings.workspace.clone());
+
+    if let Some(preset_name) = workspace_list.get(&workspace) {
+        if let Ok(overrides) = storage.get_field_json::<WorkspaceOverrides>(WorkspaceOverrides::field_name(preset_name)) {
+            let overridden = overrides.apply(&mut settings);
+
+            if !overridden.is_empty() {
+                report(
+                    Level::Info,
+                    format!("Workspace '{}' overrides the following settings: {}", workspace, overridden.join(", ")),
+                );
+            }
+        }
+    }
+
+    if let Some(overrides) = file_overrides {
+        let overridden = overrides.apply(&mut settings);
+
+        if !overridden.is_empty() {
+            report(
+                Level::Info,
+                format!("'{}' overrides the following settings: {}", SETTINGS_OVERRIDE_PATH, overridden.join(", ")),
+            );
+        }
+    }
+
+    settings = settings.validated(&workspace_list);
+
+    match toml::to_string(&settings) {
+        Ok(toml) => {
+            let text = format!(
+                "# The settings ARCropolis used on the last boot. This file is overwritten on every boot, edit '{}' to override a setting.\n{}",
+                SETTINGS_OVERRIDE_PATH, toml
+            );
+
+            if let Err(e) = std::fs::write(EFFECTIVE_SETTINGS_PATH, text) {
+                report(Level::Warn, format!("Failed to write '{}'. Reason: {:?}", EFFECTIVE_SETTINGS_PATH, e));
+            }
+        },
+        Err(e) => report(Level::Warn, format!("Failed to serialize the effective settings. Reason: {:?}", e)),
+    }
+
+    settings
+}
+
+/// The settings as they were last loaded from GLOBAL_CONFIG, so reading a setting doesn't require locking the storage
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| RwLock::new(Settings::default()));
+
+pub fn settings() -> RwLockReadGuard<'static, Settings> {
+    Lazy::force(&GLOBAL_CONFIG);
+    SETTINGS.read()
+}
+
+/// Gets the settings without waiting on anything, for places that can't afford to block such as the panic hook
+pub fn try_settings() -> Option<RwLockReadGuard<'static, Settings>> {
+    SETTINGS.try_read()
+}
+
+/// Reloads the cached settings after the storage has been modified
+pub fn reload_settings<CS: ConfigStorage>(storage: &StorageHolder<CS>) {
+    *SETTINGS.write() = effective_settings(storage);
+}
+
+/// The name of the workspace whose presets are used for mod discovery
+pub fn active_workspace() -> String {
+    settings().workspace.clone()
+}
+
 fn migrate_config_to_storage<CS: ConfigStorage>(storage: &mut StorageHolder<CS>, config: &Config) {
     info!("Converting legacy configuration file to ConfigStorage.");
 
+    let settings = Settings {
+        logging_level: config.logger.logger_level.clone(),
+        log_to_file: config.logger.log_to_file,
+        extra_paths: config.paths.extra_paths.iter().map(|path| path.display().to_string()).collect(),
+        auto_update: config.auto_update,
+        beta_updates: config.beta_updates,
+        debug: config.debug,
+        // The legacy config always had a region, so only one that was changed from the default is carried over
+        region_override: (config.region != default_region() && crate::REGIONS.contains(&config.region.as_str())).then(|| config.region.clone()),
+        ..Settings::default()
+    };
+
     storage.set_field("version", arcropolis_version()).unwrap();
-    storage.set_field("region", &config.region).unwrap();
-    storage.set_field("logging_level", &config.logger.logger_level).unwrap();
-    storage.set_field_json("extra_paths", &config.paths.extra_paths).unwrap();
-    storage.set_flag("auto_update", config.auto_update).unwrap();
-    storage.set_flag("beta_updates", config.beta_updates).unwrap();
-    storage.set_flag("debug", config.debug).unwrap();
-    storage.set_flag("log_to_file", config.logger.log_to_file).unwrap();
+    settings.save(storage).unwrap();
 }
 
 fn generate_default_config<CS: ConfigStorage>(storage: &mut StorageHolder<CS>) {
@@ -167,16 +750,13 @@ fn generate_default_config<CS: ConfigStorage>(storage: &mut StorageHolder<CS>) {
     storage.clear_storage();
 
     storage.set_field("version", arcropolis_version()).unwrap();
-    storage.set_field("region", "us_en").unwrap();
-    storage.set_field("logging_level", "Warn").unwrap();
-    storage.set_field_json("extra_paths", &Vec::<String>::new()).unwrap();
-    storage.set_flag("auto_update", true).unwrap();
     storage.set_field_json("presets", &HashSet::<Hash40>::new()).unwrap();
 
     let mut default_workspace = HashMap::<&str, &str>::new();
     default_workspace.insert("Default", "presets");
     storage.set_field_json("workspace_list", &default_workspace).unwrap();
-    storage.set_field("workspace", "Default").unwrap();
+
+    Settings::default().save(storage).unwrap();
 }
 
 fn convert_legacy_to_presets() -> HashSet<Hash40> {
@@ -271,15 +851,23 @@ impl ConfigLogger {
 }
 
 pub fn auto_update_enabled() -> bool {
-    GLOBAL_CONFIG.lock().unwrap().get_flag("auto_update")
+    settings().auto_update
 }
 
 pub fn debug_enabled() -> bool {
-    GLOBAL_CONFIG.lock().unwrap().get_flag("debug")
+    settings().debug
+}
+
+pub fn hot_reload_enabled() -> bool {
+    settings().hot_reload
+}
+
+pub fn trace_file_loads() -> bool {
+    settings().trace_file_loads
 }
 
 pub fn beta_updates() -> bool {
-    GLOBAL_CONFIG.lock().unwrap().get_flag("beta_updates")
+    settings().beta_updates
 }
 
 pub static REGION: RwLock<Region> = RwLock::new(Region::UsEnglish);
@@ -288,13 +876,38 @@ pub fn region() -> Region {
     *REGION.read()
 }
 
-pub fn region_str() -> String {
-    let region: String = GLOBAL_CONFIG
-        .lock()
-        .unwrap()
-        .get_field("region")
-        .unwrap_or_else(|_| String::from("us_en"));
-    region
+/// The name of the active region, as used for regional files (`+us_en`)
+pub fn region_str() -> &'static str {
+    let region = region();
+
+    crate::REGIONS
+        .iter()
+        .find(|name| Region::from_str(name).map_or(false, |x| x == region))
+        .copied()
+        .unwrap_or("us_en")
+}
+
+/// Every region regional mod files are accepted from, starting with the active region followed by the fallbacks
+pub fn region_chain() -> Vec<String> {
+    let mut chain = vec![region_str().to_string()];
+
+    for region in settings().region_fallbacks.iter() {
+        if !chain.contains(region) {
+            chain.push(region.clone());
+        }
+    }
+
+    chain
+}
+
+pub fn overlay_path() -> std::io::Result<PathBuf> {
+    let path = PathBuf::from("sd:/ultimate/arcropolis/overlay");
+
+    if !path.exists() {
+        std::fs::create_dir_all(&path)?;
+    }
+
+    Ok(path)
 }
 
 pub fn arc_path() -> PathBuf {
@@ -312,24 +925,15 @@ pub fn umm_path() -> PathBuf {
 }
 
 pub fn extra_paths() -> Vec<String> {
-    GLOBAL_CONFIG.lock().unwrap().get_field_json("extra_paths").unwrap_or_default()
-}
-
-pub fn logger_level() -> String {
-    let level: String = GLOBAL_CONFIG
-        .lock()
-        .unwrap()
-        .get_field("logging_level")
-        .unwrap_or_else(|_| String::from("Warn"));
-    level
+    settings().extra_paths.clone()
 }
 
 pub fn file_logging_enabled() -> bool {
-    GLOBAL_CONFIG.lock().unwrap().get_flag("log_to_file")
+    settings().log_to_file
 }
 
 pub fn legacy_discovery() -> bool {
-    GLOBAL_CONFIG.lock().unwrap().get_flag("legacy_discovery")
+    settings().legacy_discovery
 }
 
 pub struct ArcStorage(std::path::PathBuf);
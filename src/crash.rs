@@ -0,0 +1,199 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::{const_mutex, Mutex};
+use smash_arc::Hash40;
+
+use crate::{config, hashes, logging, offsets};
+
+const CRASH_REPORT_PATH: &str = "sd:/ultimate/arcropolis/crash_reports";
+
+/// Snapshot of the current session, kept up to date until ARCropolis relaunches the game itself. If it is still around on
+/// the next boot, the game crashed, or was closed from the HOME menu, which can't be told apart.
+const SESSION_PATH: &str = "sd:/ultimate/arcropolis/crash_reports/session.txt";
+
+static ENABLED_MODS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static PLUGINS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Hash of the last file that went through the resource service, 0 if none did yet
+static LAST_LOAD: AtomicU64 = AtomicU64::new(0);
+
+static BOOTED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the session ended cleanly, after which the snapshot must not be written again
+static ENDED: AtomicBool = AtomicBool::new(false);
+
+/// Held while the snapshot is written, so that it can't be written again right after the session ended
+static SESSION_LOCK: Mutex<()> = const_mutex(());
+
+pub fn set_enabled_mods(mods: Vec<PathBuf>) {
+    *ENABLED_MODS.lock() = mods;
+}
+
+pub fn add_plugin(path: &Path) {
+    PLUGINS.lock().push(path.to_path_buf());
+}
+
+pub fn set_last_load(hash: Hash40) {
+    LAST_LOAD.store(hash.0, Ordering::Relaxed);
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)
+}
+
+/// Builds the content of a crash report. This runs from the panic hook, so nothing in here is allowed to block on a lock
+/// the panicking thread could be holding.
+fn build_report(reason: &str) -> String {
+    let mut report = String::new();
+
+    let _ = writeln!(report, "ARCropolis crash report");
+    let _ = writeln!(report, "Reason: {}", reason);
+    let _ = writeln!(report, "Time: {}", logging::format_time_string(now()));
+    let _ = writeln!(report, "ARCropolis version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "Game version: {}", crate::get_version_string());
+    let _ = writeln!(report, "Region: {}", config::region_str());
+    let _ = writeln!(report, "Booted: {}", BOOTED.load(Ordering::SeqCst));
+
+    match config::try_settings() {
+        Some(settings) => {
+            let _ = writeln!(report, "Workspace: {}", settings.workspace);
+        },
+        None => {
+            let _ = writeln!(report, "Workspace: unavailable");
+        },
+    }
+
+    match LAST_LOAD.load(Ordering::Relaxed) {
+        0 => {
+            let _ = writeln!(report, "Last file loaded: none");
+        },
+        hash => {
+            let name = hashes::try_find(Hash40(hash)).unwrap_or("unknown");
+            let _ = writeln!(report, "Last file loaded: {} ({:#x})", name, hash);
+        },
+    }
+
+    match ENABLED_MODS.try_lock() {
+        Some(mods) => {
+            let _ = writeln!(report, "\nEnabled mods ({}):", mods.len());
+            for path in mods.iter() {
+                let _ = writeln!(report, "    {}", path.display());
+            }
+        },
+        None => {
+            let _ = writeln!(report, "\nEnabled mods: unavailable");
+        },
+    }
+
+    match PLUGINS.try_lock() {
+        Some(plugins) => {
+            let _ = writeln!(report, "\nChainloaded plugins ({}):", plugins.len());
+            for path in plugins.iter() {
+                let _ = writeln!(report, "    {}", path.display());
+            }
+        },
+        None => {
+            let _ = writeln!(report, "\nChainloaded plugins: unavailable");
+        },
+    }
+
+    let _ = writeln!(report, "\nOffsets:");
+    let _ = writeln!(report, "{}", offsets::describe().unwrap_or_else(|| String::from("Not found yet")));
+
+    let _ = writeln!(report, "Recent log records:");
+    for record in logging::recent_records() {
+        let _ = write!(report, "{}", record);
+    }
+
+    report
+}
+
+/// Writes a crash report to the SD card, returning where it was written
+pub fn write_report(reason: &str) -> Option<PathBuf> {
+    let _ = std::fs::create_dir_all(CRASH_REPORT_PATH);
+
+    let path = Path::new(CRASH_REPORT_PATH).join(format!("{}.txt", logging::format_time_string(now())));
+
+    match std::fs::write(&path, build_report(reason)) {
+        Ok(_) => Some(path),
+        Err(e) => {
+            println!("[arcropolis] Failed to write crash report to '{}'. Reason: {:?}", path.display(), e);
+            None
+        },
+    }
+}
+
+fn session_reason() -> &'static str {
+    if BOOTED.load(Ordering::SeqCst) {
+        "The game crashed or was closed after reaching the title screen"
+    } else {
+        "The game did not reach the title screen"
+    }
+}
+
+/// Turns the snapshot of the previous session into a crash report if it did not end cleanly, then starts keeping a
+/// snapshot of this session. The snapshot is refreshed less often once the title screen shows up.
+pub fn init() {
+    if Path::new(SESSION_PATH).exists() {
+        let path = Path::new(CRASH_REPORT_PATH).join(format!("{}_unclean.txt", logging::format_time_string(now())));
+
+        match std::fs::rename(SESSION_PATH, &path) {
+            Ok(_) => warn!(
+                "The previous session did not end cleanly, the game crashed or was closed from the HOME menu. A crash report was written to '{}'.",
+                path.display()
+            ),
+            Err(e) => error!("Failed to keep the report of the previous session. Reason: {:?}", e),
+        }
+    }
+
+    let _ = std::fs::create_dir_all(CRASH_REPORT_PATH);
+
+    let result = std::thread::Builder::new().stack_size(0x40000).spawn(|| {
+        let mut last_state = None;
+
+        loop {
+            {
+                let _guard = SESSION_LOCK.lock();
+
+                if ENDED.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                // Only rewrite the snapshot if something was logged, the offsets were found or the boot finished since the last one
+                let state = Some((logging::record_count(), offsets::ready(), BOOTED.load(Ordering::SeqCst)));
+                if state != last_state {
+                    let _ = std::fs::write(SESSION_PATH, build_report(session_reason()));
+                    last_state = state;
+                }
+            }
+
+            let interval = if BOOTED.load(Ordering::SeqCst) { 10000 } else { 2000 };
+            std::thread::sleep(Duration::from_millis(interval));
+        }
+    });
+
+    if let Err(e) = result {
+        error!("Failed to start the session snapshot thread. Reason: {:?}", e);
+    }
+}
+
+/// Removes the snapshot of this session, as it ended in a way that doesn't call for a report. This is called when the game is
+/// relaunched, and from the panic hook once the panic has its own report.
+pub fn end_session() {
+    ENDED.store(true, Ordering::SeqCst);
+
+    // The panicking thread could be the one holding the lock, in which case the snapshot is removed anyway
+    let _guard = SESSION_LOCK.try_lock_for(Duration::from_secs(1));
+    let _ = std::fs::remove_file(SESSION_PATH);
+}
+
+/// Called once the title screen shows up, which means the boot went fine
+pub fn mark_booted() {
+    BOOTED.store(true, Ordering::SeqCst);
+}
@@ -25,6 +25,7 @@ use crate::{
 mod discover;
 mod utils;
 pub use discover::*;
+pub mod watcher;
 pub mod loaders;
 pub use loaders::*;
 
@@ -48,6 +49,7 @@ pub struct CachedFilesystem {
     hash_lookup: HashMap<Hash40, PathBuf>,
     hash_size_cache: HashMap<Hash40, usize>,
     incoming_load: Option<Hash40>,
+    incoming_redirect: Option<(Hash40, api::Redirect)>,
     bytes_remaining: usize,
     current_nus3bank_id: u32,
     nus3banks: HashMap<Hash40, u32>,
@@ -72,6 +74,10 @@ impl CachedFilesystem {
             // Read the file data and map it to a json. If that fails, just skip this current JSON.
             let cfg = ModConfig::from_file_json(&full_path).ok();
 
+            if let Ok(json) = std::fs::read_to_string(&full_path) {
+                hashes::learn_from_json(&json);
+            }
+
             if let Some(cfg) = cfg {
                 current.merge(cfg);
             } else {
@@ -128,6 +134,21 @@ impl CachedFilesystem {
         set
     }
 
+    /// Add the files picked in the conflict menu to the virtual tree, as discovery left them out of every mod root.
+    /// Returns the local path and the size of each file, keyed by hash.
+    fn initialize_resolved_files(api_tree: &mut Tree<ApiLoader>) -> HashMap<Hash40, (PathBuf, usize)> {
+        let mut files = HashMap::new();
+
+        for (full_path, local) in discover::resolved_files() {
+            if let Some(hash) = utils::add_physical_file(api_tree, full_path, local) {
+                let size = std::fs::metadata(full_path).map(|meta| meta.len() as usize).unwrap_or(0);
+                files.insert(hash, (local.clone(), size));
+            }
+        }
+
+        files
+    }
+
     /// Parse a pending API call and add it to the API tree. This function returns the hash, as well as the size (if needed)
     /// so that the caller can insert those into the global structs depending on the time that this call is handled
     fn handle_panding_api_call(api_tree: &mut Tree<ApiLoader>, pending: api::PendingApiCall) -> ApiCallResult {
@@ -183,6 +204,9 @@ impl CachedFilesystem {
         // Load all of the user configs into the main config
         Self::load_remaining_configs(&mut config, &launchpad);
 
+        // Load the configs that plugins have submitted so far, so that they are also taken into account when unsharing
+        api::merge_pending_configs(&mut config, false);
+
         // Collect all of the NUS3BANK dependencies that audio files have in order to be unshared
         // Note that we pass the unshare blacklist because if the NUS3AUDIO files are blacklisted then we shouldn't unshare the
         // actual nus3bank either
@@ -204,6 +228,12 @@ impl CachedFilesystem {
             }
         }
 
+        // Add the files that won a conflict, which discovery kept out of the mod roots
+        for (hash, (local, size)) in Self::initialize_resolved_files(&mut api_tree) {
+            hashed_paths.insert(hash, local);
+            hashed_sizes.insert(hash, size);
+        }
+
         // Add all of the NUS3BANKs that our NUS3AUDIOs depend on to the API tree
         for dep in nus3audio_deps {
             let hash = utils::add_file_to_api_tree(&mut api_tree, "api:/patch-nus3bank", &dep, ApiCallback::None);
@@ -229,6 +259,12 @@ impl CachedFilesystem {
             }
         }
 
+        // Make room for the largest file that the redirect callbacks registered so far may load instead
+        for (hash, entry) in api::REDIRECT_CALLBACKS.read().iter() {
+            let size = hashed_sizes.entry(*hash).or_insert(0);
+            *size = (*size).max(entry.max_size);
+        }
+
         // Set the global flag that we are initialized (referenced by API)
         IS_INIT.store(true, Ordering::SeqCst);
 
@@ -239,6 +275,7 @@ impl CachedFilesystem {
             hash_lookup: hashed_paths,
             hash_size_cache: hashed_sizes,
             incoming_load: None,
+            incoming_redirect: None,
             bytes_remaining: 0,
             current_nus3bank_id: 7420,
             nus3banks: HashMap::new(),
@@ -352,6 +389,11 @@ impl CachedFilesystem {
                 hash.0
             );
         }
+
+        if let Some(hash) = hash {
+            crate::crash::set_last_load(hash);
+        }
+
         self.incoming_load = hash;
         if let Some(hash) = hash {
             self.bytes_remaining = *self.hash_size_cache.get(&hash).unwrap_or(&0);
@@ -365,6 +407,48 @@ impl CachedFilesystem {
         self.incoming_load.take()
     }
 
+    // Sets where the incoming file should be loaded from instead, as decided by a redirect callback
+    pub fn set_incoming_redirect(&mut self, hash: Hash40, redirect: api::Redirect) {
+        self.incoming_redirect = Some((hash, redirect));
+    }
+
+    // Gets the redirect for the file being replaced, if one was set for it
+    pub fn take_redirect(&mut self, hash: Hash40) -> Option<api::Redirect> {
+        match self.incoming_redirect.take() {
+            Some((incoming, redirect)) if incoming == hash => Some(redirect),
+            _ => None,
+        }
+    }
+
+    // Load the file data a redirect points to into a pre-allocated buffer
+    pub fn load_redirect_into(&self, redirect: &api::Redirect, mut buffer: &mut [u8]) -> Option<usize> {
+        let data = match redirect {
+            api::Redirect::Hash(hash) if self.hash_lookup.contains_key(hash) => self.load(*hash),
+            api::Redirect::Hash(hash) => match resource::arc().get_file_contents(*hash, config::region()) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    error!("Failed to load redirected file '{}' ({:#x}). Reason: {:?}", hashes::find(*hash), hash.0, e);
+                    None
+                },
+            },
+            api::Redirect::Path(path) => match std::fs::read(path) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    error!("Failed to load redirected file {}. Reason: {:?}", path.display(), e);
+                    None
+                },
+            },
+        }?;
+
+        if buffer.len() < data.len() {
+            error!("The size of the redirected file data is larger than the size of the provided buffer. Was the redirect registered with a large enough size?");
+            None
+        } else {
+            buffer.write_all(&data).unwrap();
+            Some(data.len())
+        }
+    }
+
     // Subtracts the amount of bytes remanining from the current load.
     // This prevents multiloads on the same file
     pub fn sub_remaining_bytes(&mut self, count: usize) -> Option<Hash40> {
@@ -412,6 +496,9 @@ impl CachedFilesystem {
 
     /// Goes through and performs the required file manipulation in order to load mods
     pub fn process_mods(&mut self) {
+        // Anything submitted past this point would come too late to be applied
+        api::merge_pending_configs(&mut self.config, true);
+
         let mut context = LoadedArc::make_addition_context();
         let mut search_context = LoadedSearchSection::make_context();
 
@@ -516,16 +603,115 @@ impl CachedFilesystem {
 
         self.hash_lookup.insert(hash, path);
         if let Some(size) = size {
-            if let Some(old_size) = self.patch_file(hash, size) {
-                if let Some(size_mut) = self.hash_size_cache.get_mut(&hash) {
-                    if *size_mut > old_size {
-                        *size_mut = old_size;
-                    }
-                } else {
-                    self.hash_size_cache.insert(hash, size);
+            self.update_file_size(hash, size);
+        }
+    }
+
+    /// Patches the LoadedArc for a file that is now `size` bytes and updates its cached size. The LoadedArc is only patched again
+    /// if the file grew past the size the game expects for it.
+    fn update_file_size(&mut self, hash: Hash40, size: usize) {
+        match self.patch_file(hash, size) {
+            // Same rules as patch_files: the cache holds the smallest of the vanilla size and the modded size
+            Some(old_size) => {
+                let cached_size = self.hash_size_cache.entry(hash).or_insert(old_size);
+                if *cached_size > old_size {
+                    *cached_size = old_size;
+                }
+            },
+            // The file still fits, so whatever was cached for it holds
+            None => {
+                self.hash_size_cache.entry(hash).or_insert(size);
+            },
+        }
+    }
+
+    /// Registers a file written to the overlay during the current session, so that it is used the next time the game loads it.
+    /// Files that the game has already loaded are left alone, since swapping them out from under it isn't safe.
+    pub fn register_physical_file(&mut self, local: &Path, full_path: &Path) -> bool {
+        let arc = resource::arc();
+
+        let hash = match local.smash_hash() {
+            Ok(hash) => hash,
+            Err(e) => {
+                error!("Failed to get hash for overlay file {}. Reason: {:?}", local.display(), e);
+                return false;
+            },
+        };
+
+        let filepath_index = match arc.get_file_path_index_from_hash(hash) {
+            Ok(index) => index,
+            Err(_) => {
+                warn!(
+                    "Overlay file '{}' does not replace a file in the data.arc. A reboot is required for it to be added.",
+                    local.display()
+                );
+                return false;
+            },
+        };
+
+        if resource::filesystem_info().get_loaded_filepaths()[usize::from(filepath_index)].is_loaded != 0 {
+            warn!(
+                "Overlay file '{}' replaces a file which is already loaded. It will be used after a reboot.",
+                local.display()
+            );
+            return false;
+        }
+
+        if utils::add_physical_file(self.loader.virt_mut(), full_path, local).is_none() {
+            return false;
+        }
+
+        self.hash_lookup.insert(hash, local.to_path_buf());
+        self.refresh_file(hash);
+
+        info!("Registered overlay file '{}' ({:#x}).", local.display(), hash.0);
+        true
+    }
+
+    /// Makes sure a file can hold `size` bytes once it has been loaded. Used for files which aren't modded but can be redirected.
+    pub fn reserve_size(&mut self, hash: Hash40, size: usize) {
+        self.update_file_size(hash, size);
+    }
+
+    /// Re-queries the size of a modded file and patches the LoadedArc again if the file has grown since it was last patched
+    pub fn refresh_file(&mut self, hash: Hash40) -> Option<usize> {
+        let size = self.hash_lookup.get(&hash).and_then(|path| self.loader.query_max_filesize(path))?;
+        self.update_file_size(hash, size);
+        Some(size)
+    }
+
+    /// Gets every hash that the filesystem replaces
+    pub fn modded_hashes(&self) -> Vec<Hash40> {
+        self.hash_lookup.keys().copied().collect()
+    }
+
+    /// Gets the physical files backing every modded hash, including patch files which map to the file they generate.
+    /// Also returns the mod roots that those files were discovered in.
+    pub fn watch_list(&self) -> (HashMap<PathBuf, Vec<Hash40>>, HashSet<PathBuf>) {
+        let mut files: HashMap<PathBuf, Vec<Hash40>> = HashMap::new();
+        let mut roots = HashSet::new();
+
+        for (hash, local) in self.hash_lookup.iter() {
+            let actual = match self.loader.query_actual_path(local) {
+                Some(actual) if actual.is_file() => actual,
+                _ => continue,
+            };
+
+            // The mod root is whatever is left of the physical path once the local path has been removed
+            if let (Some(actual), Some(local)) = (actual.to_str(), local.to_str()) {
+                if let Some(root) = actual.strip_suffix(local) {
+                    roots.insert(PathBuf::from(root));
                 }
             }
+
+            files.entry(actual).or_default().push(*hash);
         }
+
+        for (hash, path) in self.loader.virt().loader.patch_files() {
+            files.entry(path.clone()).or_default().push(hash);
+        }
+
+        (files, roots)
     }
 
     /// Gets the cached size
@@ -656,6 +842,55 @@ impl GlobalFilesystem {
         }
     }
 
+    pub fn set_incoming_redirect(&mut self, hash: Hash40, redirect: api::Redirect) {
+        match self {
+            Self::Initialized(fs) => fs.set_incoming_redirect(hash, redirect),
+            _ => error!(
+                "Cannot redirect the incoming load for '{}' ({:#x}) because the filesystem is not initialized!",
+                hashes::find(hash),
+                hash.0
+            ),
+        }
+    }
+
+    pub fn take_redirect(&mut self, hash: Hash40) -> Option<api::Redirect> {
+        match self {
+            Self::Initialized(fs) => fs.take_redirect(hash),
+            _ => None,
+        }
+    }
+
+    pub fn load_redirect_into(&self, redirect: &api::Redirect, buffer: &mut [u8]) -> Option<usize> {
+        match self {
+            Self::Initialized(fs) => fs.load_redirect_into(redirect, buffer),
+            _ => {
+                error!("Cannot load redirected data because the filesystem is not initialized!");
+                None
+            },
+        }
+    }
+
+    pub fn register_physical_file(&mut self, local: &Path, full_path: &Path) -> bool {
+        match self {
+            Self::Initialized(fs) => fs.register_physical_file(local, full_path),
+            _ => {
+                error!("Cannot register '{}' because the filesystem is not initialized!", local.display());
+                false
+            },
+        }
+    }
+
+    pub fn reserve_size(&mut self, hash: Hash40, size: usize) {
+        match self {
+            Self::Initialized(fs) => fs.reserve_size(hash, size),
+            _ => error!(
+                "Cannot reserve a size for '{}' ({:#x}) because the filesystem is not initialized!",
+                hashes::find(hash),
+                hash.0
+            ),
+        }
+    }
+
     pub fn patch_files(&mut self) {
         match self {
             Self::Initialized(fs) => fs.patch_files(),
@@ -718,6 +953,34 @@ impl GlobalFilesystem {
         }
     }
 
+    pub fn modded_hashes(&self) -> Vec<Hash40> {
+        match self {
+            Self::Initialized(fs) => fs.modded_hashes(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn watch_list(&self) -> (HashMap<PathBuf, Vec<Hash40>>, HashSet<PathBuf>) {
+        match self {
+            Self::Initialized(fs) => fs.watch_list(),
+            _ => (HashMap::new(), HashSet::new()),
+        }
+    }
+
+    pub fn refresh_file(&mut self, hash: Hash40) -> Option<usize> {
+        match self {
+            Self::Initialized(fs) => fs.refresh_file(hash),
+            _ => {
+                error!(
+                    "Cannot refresh '{}' ({:#x}) because the filesystem is not initialized!",
+                    hashes::find(hash),
+                    hash.0
+                );
+                None
+            },
+        }
+    }
+
     pub fn get_sum_size(&self) -> Option<usize> {
         match self {
             Self::Initialized(fs) => Some(fs.get_sum_size()),
@@ -3,17 +3,17 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use orbits::{ConflictHandler, ConflictKind, FileLoader, LaunchPad, StandardLoader, Tree};
 use skyline::nn::{self, ro::*};
 use smash_arc::Hash40;
 
-use crate::{chainloader::*, config};
+use crate::{chainloader::*, config, PathExtension};
 
 static PRESET_HASHES: Lazy<HashSet<Hash40>> = Lazy::new(|| {
     let mut storage = config::GLOBAL_CONFIG.lock().unwrap();
 
-    let workspace_name: String = storage.get_field("workspace").unwrap_or_else(|_| "Default".to_string());
+    let workspace_name: String = config::active_workspace();
     let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
 
     // Get the name of the preset file from the workspace list
@@ -37,6 +37,64 @@ static PRESET_HASHES: Lazy<HashSet<Hash40>> = Lazy::new(|| {
     presets
 });
 
+/// Files read by ARCropolis itself rather than loaded by the game
+pub(crate) static RESERVED_NAMES: &[&str] = &[
+    "config.json",
+    "plugin.nro",
+];
+
+/// Extensions of the files merged into the game's files instead of replacing them
+pub(crate) static PATCH_EXTENSIONS: &[&str] = &[
+    "prcx",
+    "prcxml",
+    "stdatx",
+    "stdatxml",
+    "stprmx",
+    "stprmxml",
+
+    "xmsbt",
+
+    "patch3audio"
+];
+
+/// Regional files are only loaded for the active region and its fallbacks. This is a static so that the ignore callback
+/// doesn't have to capture anything.
+static REGION_CHAIN: Lazy<Vec<String>> = Lazy::new(config::region_chain);
+
+/// Files picked in the conflict menu, as their full path and their path relative to the mod. They are kept out of every
+/// mod root during discovery, and loaded straight from the winning mod instead.
+static RESOLVED_FILES: OnceCell<Vec<(PathBuf, PathBuf)>> = OnceCell::new();
+
+/// Hashes of the resolved files without their region, so that the ignore callback doesn't have to hash each of them again
+static RESOLVED_HASHES: OnceCell<HashSet<Hash40>> = OnceCell::new();
+
+pub fn resolved_files() -> &'static [(PathBuf, PathBuf)] {
+    RESOLVED_FILES.get().map_or(&[], |files| files.as_slice())
+}
+
+/// Only the resolutions picking a mod that is going to be loaded are honored
+fn find_resolved_files<F: Fn(&Path) -> bool>(filter: F) -> Vec<(PathBuf, PathBuf)> {
+    config::ConflictResolutions::active()
+        .winners
+        .into_iter()
+        .filter_map(|(local, root)| {
+            let root = PathBuf::from(root);
+            let full_path = root.join(&local);
+
+            if !filter(&root) {
+                return None;
+            }
+
+            if !full_path.is_file() {
+                warn!("'{}' was picked to resolve a conflict but does not exist anymore.", full_path.display());
+                return None;
+            }
+
+            Some((full_path, PathBuf::from(local)))
+        })
+        .collect()
+}
+
 pub fn perform_discovery() -> LaunchPad<StandardLoader> {
     let is_emulator = unsafe { skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as u64 } == 0x8004000;
 
@@ -77,33 +135,22 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
 
         let is_out_of_region = if let Some(index) = name.find('+') {
             let (_, end) = name.split_at(index + 1);
-            !end.starts_with(&config::region_str())
+            !REGION_CHAIN.iter().any(|region| end.starts_with(region.as_str()))
         } else {
             false
         };
 
-        is_root || is_dot || is_out_of_region
+        // Matched by hash, so that picking the regional file of a mod also keeps the non-regional files of the others out
+        let is_resolved = RESOLVED_HASHES
+            .get()
+            .map_or(false, |hashes| !hashes.is_empty() && path.smash_hash().map_or(false, |hash| hashes.contains(&hash)));
+
+        is_root || is_dot || is_out_of_region || is_resolved
     };
 
     let collect = |x: &Path| {
         match x.file_name() {
             Some(name) if let Some(name) = name.to_str() => {
-                static RESERVED_NAMES: &[&str] = &[
-                    "config.json",
-                    "plugin.nro",
-                ];
-                static PATCH_EXTENSIONS: &[&str] = &[
-                    "prcx",
-                    "prcxml",
-                    "stdatx",
-                    "stdatxml",
-                    "stprmx",
-                    "stprmxml",
-
-                    "xmsbt",
-
-                    "patch3audio"
-                ];
                 RESERVED_NAMES.contains(&name) || PATCH_EXTENSIONS.iter().any(|x| name.ends_with(x))
             },
             _ => false
@@ -134,7 +181,7 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
             .collect();
 
         // Get the workspace name and workspace list
-        let workspace_name: String = storage.get_field("workspace").unwrap_or_else(|_| "Default".to_string());
+        let workspace_name: String = config::active_workspace();
         let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
 
         // Get the preset name from the workspace list
@@ -157,16 +204,24 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
         storage.set_field_json("mod_cache", &new_cache).unwrap();
     }
 
+    let _ = RESOLVED_FILES.set(find_resolved_files(filter));
+    let _ = RESOLVED_HASHES.set(resolved_files().iter().filter_map(|(_, local)| local.smash_hash().ok()).collect());
+
+    if !resolved_files().is_empty() {
+        info!("{} file conflict(s) were resolved in the conflict menu.", resolved_files().len());
+    }
+
     let mut launchpad = LaunchPad::new(StandardLoader, ConflictHandler::NoRoot);
 
     launchpad.collecting(collect);
     launchpad.ignoring(ignore);
 
-    let mut conflicts = if std::fs::try_exists(arc_path).unwrap_or(false) {
-        launchpad.discover_in_root(config::arc_path())
-    } else {
-        Vec::new()
-    };
+    // The overlay goes first, so that what plugins wrote to it takes priority over every mod
+    let mut conflicts = discover_overlay(&mut launchpad);
+
+    if std::fs::try_exists(arc_path).unwrap_or(false) {
+        conflicts.extend(launchpad.discover_in_root(config::arc_path()));
+    }
 
     if std::fs::try_exists(&umm_path).unwrap_or(false) {
         conflicts.extend(launchpad.discover_roots(&umm_path, 1, filter));
@@ -178,6 +233,12 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
         }
     }
 
+    crate::crash::set_enabled_mods(enabled_mods(&umm_path, filter));
+
+    if let Ok(overlay_path) = config::overlay_path() {
+        conflicts.retain(|conflict| !involves_overlay(conflict, &overlay_path));
+    }
+
     let should_prompt = !conflicts.is_empty();
 
     for conflict in conflicts.into_iter() {
@@ -215,11 +276,11 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
         launchpad.collecting(collect);
         launchpad.ignoring(ignore);
 
-        let mut conflicts = if std::fs::try_exists(arc_path).unwrap_or(false) {
-            launchpad.discover_in_root(config::arc_path())
-        } else {
-            Vec::new()
-        };
+        let mut conflicts = discover_overlay(&mut launchpad);
+
+        if std::fs::try_exists(arc_path).unwrap_or(false) {
+            conflicts.extend(launchpad.discover_in_root(config::arc_path()));
+        }
 
         if std::fs::try_exists(umm_path).unwrap_or(false) {
             conflicts.extend(launchpad.discover_roots(config::umm_path(), 1, filter));
@@ -231,6 +292,10 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
             }
         }
 
+        if let Ok(overlay_path) = config::overlay_path() {
+            conflicts.retain(|conflict| !involves_overlay(conflict, &overlay_path));
+        }
+
         let mut conflict_map: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
 
         for conflict in conflicts.into_iter() {
@@ -294,6 +359,42 @@ pub fn perform_discovery() -> LaunchPad<StandardLoader> {
     launchpad
 }
 
+/// Discovers the files plugins wrote to overlay:/ the same way as a mod root
+fn discover_overlay(launchpad: &mut LaunchPad<StandardLoader>) -> Vec<ConflictKind> {
+    match config::overlay_path() {
+        Ok(overlay_path) => launchpad.discover_in_root(overlay_path),
+        Err(e) => {
+            error!("Failed to create the overlay directory, its files will not be loaded. Reason: {:?}", e);
+            Vec::new()
+        },
+    }
+}
+
+/// The overlay is meant to take priority over every mod, so the files it shares with them aren't conflicts to report
+fn involves_overlay(conflict: &ConflictKind, overlay_path: &Path) -> bool {
+    match conflict {
+        ConflictKind::StandardConflict { error_root, source_root, .. } => error_root.starts_with(overlay_path) || source_root.starts_with(overlay_path),
+        ConflictKind::RootConflict(root_path, kept) => root_path.starts_with(overlay_path) || kept.starts_with(overlay_path),
+    }
+}
+
+/// Lists the mod folders that passed the filter during discovery, to be included in crash reports
+fn enabled_mods<F: Fn(&Path) -> bool>(umm_path: &Path, filter: F) -> Vec<PathBuf> {
+    let mut mods = Vec::new();
+
+    if std::fs::try_exists(config::arc_path()).unwrap_or(false) {
+        mods.push(config::arc_path());
+    }
+
+    for root in std::iter::once(umm_path.to_path_buf()).chain(config::extra_paths().into_iter().map(PathBuf::from)) {
+        if let Ok(entries) = std::fs::read_dir(&root) {
+            mods.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir() && filter(path)));
+        }
+    }
+
+    mods
+}
+
 fn mount_prebuilt_nrr<A: FileLoader>(tree: &Tree<A>) -> Result<Option<RegistrationInfo>, NrrRegistrationFailedError>
 where
     <A as FileLoader>::ErrorType: std::fmt::Debug,
@@ -315,6 +416,16 @@ where
 }
 
 pub fn load_and_run_plugins(plugins: &[(PathBuf, PathBuf)]) {
+    let (chainload_plugins, plugin_blocklist) = {
+        let settings = config::settings();
+        (settings.chainload_plugins, settings.plugin_blocklist.clone())
+    };
+
+    if !chainload_plugins {
+        info!("Chainloading plugins is disabled in the configuration, skipping {} plugin(s).", plugins.len());
+        return;
+    }
+
     let mut plugin_nrr = NrrBuilder::new();
 
     let modules: Vec<NroBuilder> = plugins
@@ -322,10 +433,21 @@ pub fn load_and_run_plugins(plugins: &[(PathBuf, PathBuf)]) {
         .filter_map(|(root, local)| {
             let full_path = root.join(local);
 
+            let is_blocked = root
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| plugin_blocklist.iter().any(|blocked| blocked == name));
+
+            if is_blocked {
+                info!("Skipping plugin at '{}', its mod is in the plugin blocklist.", full_path.display());
+                return None;
+            }
+
             if full_path.exists() && full_path.ends_with("plugin.nro") {
                 match NroBuilder::open(&full_path) {
                     Ok(builder) => {
                         info!("Loaded plugin at '{}' for chainloading.", full_path.display());
+                        crate::crash::add_plugin(&full_path);
                         plugin_nrr.add_module(&builder);
                         Some(builder)
                     },
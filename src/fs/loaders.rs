@@ -52,6 +52,7 @@ enum ApiLoadType {
     Generic,
     Stream,
     Extension,
+    PhysicalFile,
 }
 
 impl ApiLoadType {
@@ -70,13 +71,15 @@ impl ApiLoadType {
             Ok(ApiLoadType::Stream)
         } else if root.ends_with("extension-cb") {
             Ok(ApiLoadType::Extension)
+        } else if root.ends_with("physical-file") {
+            Ok(ApiLoadType::PhysicalFile)
         } else {
             Err(ApiLoaderError::Other(format!("Cannot find ApiLoadType for root {}", root.display())))
         }
     }
 
     pub fn path_exists(self, _local: &Path) -> bool {
-        matches!(self, ApiLoadType::Nus3bankPatch)
+        matches!(self, ApiLoadType::Nus3bankPatch | ApiLoadType::PhysicalFile)
     }
 
     pub fn get_file_size(self, local: &Path) -> Option<usize> {
@@ -88,6 +91,12 @@ impl ApiLoadType {
                     .and_then(|hash| arc.get_file_data_from_hash(hash, config::region()).ok())
                     .map(|x| x.decomp_size as usize)
             },
+            ApiLoadType::PhysicalFile => local
+                .smash_hash()
+                .ok()
+                .and_then(ApiLoader::get_physical_file_for_hash)
+                .and_then(|path| std::fs::metadata(path).ok())
+                .map(|meta| meta.len() as usize),
             _ => None,
         }
     }
@@ -103,6 +112,7 @@ impl ApiLoadType {
                     Ok(FileEntryType::File)
                 }
             },
+            ApiLoadType::PhysicalFile => Ok(FileEntryType::File),
             _ => Err(ApiLoaderError::Other("Unimplemented ApiLoadType!".to_string())),
         }
     }
@@ -317,6 +327,16 @@ impl ApiLoadType {
                 Ok((file_size, vec))
             },
             ApiLoadType::Stream => Err(ApiLoaderError::InvalidCb),
+            ApiLoadType::PhysicalFile => {
+                let path = if let Some(path) = ApiLoader::get_physical_file_for_hash(local.smash_hash()?) {
+                    path
+                } else {
+                    return Err(ApiLoaderError::Other("No physical file found for file in physical file root!".to_string()));
+                };
+
+                let data = std::fs::read(path)?;
+                Ok((data.len(), data))
+            },
             _ => Err(ApiLoaderError::Other("Unimplemented ApiLoadType!".to_string()))
         }
     }
@@ -347,6 +367,7 @@ pub struct ApiLoader {
     param_patches: HashMap<Hash40, Vec<PathBuf>>,
     msbt_patches: HashMap<Hash40, Vec<PathBuf>>,
     nus3audio_patches: HashMap<Hash40, Vec<PathBuf>>,
+    physical_files: HashMap<Hash40, PathBuf>,
 }
 
 unsafe impl Send for ApiLoader {}
@@ -457,6 +478,29 @@ impl ApiLoader {
         }
     }
 
+    /// Gets every patch file along with the hash of the file that it patches
+    pub fn patch_files(&self) -> impl Iterator<Item = (Hash40, &PathBuf)> {
+        self.param_patches
+            .iter()
+            .chain(self.msbt_patches.iter())
+            .chain(self.nus3audio_patches.iter())
+            .flat_map(|(hash, paths)| paths.iter().map(move |path| (*hash, path)))
+    }
+
+    pub fn get_physical_file_for_hash(hash: Hash40) -> Option<&'static PathBuf> {
+        let filesystem = unsafe { &*crate::GLOBAL_FILESYSTEM.data_ptr() };
+
+        let cached = filesystem.get();
+
+        cached.virt().loader.physical_files.get(&hash)
+    }
+
+    /// Sets the file on the SD card that a hash registered under the physical file root loads from.
+    /// Returns true if the hash was not registered before.
+    pub fn insert_physical_file(&mut self, hash: Hash40, path: &Path) -> bool {
+        self.physical_files.insert(hash, path.to_path_buf()).is_none()
+    }
+
     fn get_stream_cb_path(&self, local: &Path) -> Option<String> {
         if let Some((root_path, callback)) = self.use_virtual_file(local) {
             let result = match ApiLoadType::from_root(root_path) {
@@ -546,6 +590,12 @@ impl FileLoader for ApiLoader {
     fn get_actual_path(&self, root_path: &Path, local_path: &Path) -> Option<PathBuf> {
         if root_path.ends_with("stream-cb") {
             Some(self.get_stream_cb_path(local_path).map_or(root_path.join(local_path), PathBuf::from))
+        } else if root_path.ends_with("physical-file") {
+            local_path
+                .smash_hash()
+                .ok()
+                .and_then(|hash| self.physical_files.get(&hash).cloned())
+                .or_else(|| Some(root_path.join(local_path)))
         } else {
             Some(root_path.join(local_path))
         }
@@ -25,9 +25,11 @@ where
     // To solve this I store the hash of every file which has a regional variant which has been found, and then if a non-regional variant is found
     // it is ignored
     // - blujay
-    let mut regional_overrides = HashSet::new();
-    let mut size_map = HashMap::new();
-    let mut path_map = HashMap::new();
+    // With region fallbacks there can be several regional variants of the same file. A variant for the active region always wins,
+    // then the non-regional file, and only then the variant whose fallback region comes first in the region chain. Every file found
+    // for a hash is remembered, and the winner is only picked once the whole tree has been walked so that the order doesn't matter.
+    let regions = crate::config::region_chain();
+    let mut candidates: HashMap<Hash40, Vec<(usize, usize, PathBuf)>> = HashMap::new();
     tree.walk_paths(|node, ty| {
         if !ty.is_file() {
             return;
@@ -36,18 +38,21 @@ where
         if let Some(size) = tree.query_filesize(node.get_local()) {
             match node.get_local().smash_hash() {
                 Ok(hash) => {
-                    if regional_overrides.contains(&hash) {
-                        return;
-                    }
+                    let region_rank = node
+                        .get_local()
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.find('+').map(|idx| &name[idx + 1..]))
+                        .map(|region| regions.iter().position(|x| region.starts_with(x.as_str())).unwrap_or(regions.len()));
 
-                    let is_regional_variant = if let Some(node) = node.get_local().to_str() { node.contains('+') } else { false };
+                    // 0 for the active region, 1 for the non-regional file and 2 onwards for the fallback regions
+                    let priority = match region_rank {
+                        Some(0) => 0,
+                        None => 1,
+                        Some(rank) => rank + 1,
+                    };
 
-                    size_map.insert(hash, size);
-                    path_map.insert(hash, node.get_local().to_path_buf());
-
-                    if is_regional_variant {
-                        regional_overrides.insert(hash);
-                    }
+                    candidates.entry(hash).or_default().push((priority, size, node.get_local().to_path_buf()));
                 },
                 Err(e) => error!("Failed to get hash for {}. Reason: {:?}", node.get_local().display(), e),
             }
@@ -56,6 +61,16 @@ where
         }
     });
 
+    let mut size_map = HashMap::new();
+    let mut path_map = HashMap::new();
+
+    for (hash, files) in candidates {
+        if let Some((_, size, path)) = files.into_iter().min_by_key(|(priority, ..)| *priority) {
+            size_map.insert(hash, size);
+            path_map.insert(hash, path);
+        }
+    }
+
     (size_map, path_map)
 }
 
@@ -116,6 +131,19 @@ pub fn add_file_to_api_tree<P: AsRef<Path>, Q: AsRef<Path>>(
     }
 }
 
+/// Adds a file which is loaded straight from the SD card to the API loader, taking priority over the mod roots
+pub fn add_physical_file<P: AsRef<Path>, Q: AsRef<Path>>(tree: &mut Tree<ApiLoader>, full_path: P, local: Q) -> Option<Hash40> {
+    let local = local.as_ref();
+    let hash = local.smash_hash().ok()?;
+
+    // Registering the same file twice would only shadow the first entry with an identical one
+    if tree.loader.insert_physical_file(hash, full_path.as_ref()) {
+        add_file_to_api_tree(tree, "api:/physical-file", local, ApiCallback::None)
+    } else {
+        Some(hash)
+    }
+}
+
 /// Adds a PRC patch file and information to the API loader
 pub fn add_prc_patch<P: AsRef<Path>, Q: AsRef<Path>>(tree: &mut Tree<ApiLoader>, phys_root: P, local: Q) -> Option<Hash40> {
     let local = local.as_ref();
@@ -0,0 +1,191 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use smash_arc::Hash40;
+use walkdir::WalkDir;
+
+use crate::{hashes, replacement::reload::ReloadError, GLOBAL_FILESYSTEM};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(2000);
+
+/// How many polls a file still in use by the game is retried for before giving up on it
+const MAX_RETRIES: usize = 30;
+
+/// Every how many polls every watched directory is listed again, whether its modification time changed or not. FAT32 and
+/// exFAT don't reliably update the modification time of a directory when a file is added to it, so this is the only way
+/// new files get noticed on these.
+const FULL_RESCAN_POLLS: usize = 30;
+
+/// What we know about a file on the SD card the last time it was checked
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn from_path(path: &Path) -> Option<Self> {
+        std::fs::metadata(path).ok().map(|meta| Self {
+            // Not every filesystem reports a modification time, so fall back to the length only
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        })
+    }
+}
+
+struct Watcher {
+    /// Physical file -> the hashes which have to be reloaded when it changes
+    files: HashMap<PathBuf, Vec<Hash40>>,
+    roots: HashSet<PathBuf>,
+    stamps: HashMap<PathBuf, FileStamp>,
+    /// Modification time of every directory in the mod roots, which changes when a file is added to it
+    directories: HashMap<PathBuf, Option<SystemTime>>,
+    /// Hashes that could not be evicted because the game was still using them, along with how many times it was tried
+    retry: HashMap<Hash40, usize>,
+    /// Files that were added after boot, so that we only warn about them once
+    reported: HashSet<PathBuf>,
+    polls: usize,
+}
+
+impl Watcher {
+    fn new() -> Self {
+        let (files, roots) = GLOBAL_FILESYSTEM.read().watch_list();
+
+        let stamps = files
+            .keys()
+            .filter_map(|path| FileStamp::from_path(path).map(|stamp| (path.clone(), stamp)))
+            .collect();
+
+        // The roots are only walked once, to know which directories to keep an eye on
+        let directories = roots
+            .iter()
+            .flat_map(|root| WalkDir::new(root).into_iter().flatten())
+            .filter(|entry| entry.file_type().is_dir())
+            .map(|entry| {
+                let modified = entry.metadata().ok().and_then(|meta| meta.modified().ok());
+                (entry.into_path(), modified)
+            })
+            .collect();
+
+        let watcher = Self {
+            files,
+            roots,
+            stamps,
+            directories,
+            retry: HashMap::new(),
+            reported: HashSet::new(),
+            polls: 0,
+        };
+
+        info!(
+            "Watching {} modded file(s) across {} mod root(s) for changes.",
+            watcher.files.len(),
+            watcher.roots.len()
+        );
+
+        watcher
+    }
+
+    /// Lists the entries of the directories that were modified since the last poll, or of every directory if `full` is set.
+    /// New directories are watched from now on.
+    fn added_files(&mut self, full: bool) -> Vec<PathBuf> {
+        let modified: Vec<PathBuf> = self
+            .directories
+            .iter_mut()
+            .filter_map(|(path, previous)| {
+                let current = std::fs::metadata(path).ok().and_then(|meta| meta.modified().ok());
+                (std::mem::replace(previous, current) != current || full).then(|| path.clone())
+            })
+            .collect();
+
+        let mut added = Vec::new();
+
+        for entry in modified.iter().filter_map(|dir| std::fs::read_dir(dir).ok()).flatten().flatten() {
+            let path = entry.path();
+
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => {
+                    self.directories
+                        .entry(path)
+                        .or_insert_with(|| entry.metadata().ok().and_then(|meta| meta.modified().ok()));
+                },
+                Ok(kind) if kind.is_file() && !self.files.contains_key(&path) => added.push(path),
+                _ => {},
+            }
+        }
+
+        added
+    }
+
+    fn poll(&mut self) {
+        let mut changed: Vec<Hash40> = self.retry.keys().copied().collect();
+
+        for (path, hashes) in self.files.iter() {
+            let stamp = match FileStamp::from_path(path) {
+                Some(stamp) => stamp,
+                None => continue,
+            };
+
+            if self.stamps.insert(path.clone(), stamp).map_or(false, |previous| previous != stamp) {
+                info!("'{}' has changed on the SD card.", path.display());
+                changed.extend(hashes.iter().copied());
+            }
+        }
+
+        self.polls += 1;
+
+        for path in self.added_files(self.polls % FULL_RESCAN_POLLS == 0) {
+            if self.reported.insert(path.clone()) {
+                warn!("'{}' was added after boot. A reboot is required for it to be loaded.", path.display());
+            }
+        }
+
+        for hash in changed {
+            match crate::replacement::reload::invalidate(hash) {
+                Ok(()) => {
+                    self.retry.remove(&hash);
+                },
+                Err(ReloadError::InUse(_)) => {
+                    let attempts = self.retry.entry(hash).or_insert(0);
+                    *attempts += 1;
+
+                    if *attempts >= MAX_RETRIES {
+                        warn!(
+                            "'{}' ({:#x}) is still in use after {} attempts, giving up on reloading it.",
+                            hashes::find(hash),
+                            hash.0,
+                            attempts
+                        );
+                        self.retry.remove(&hash);
+                    } else {
+                        debug!("'{}' ({:#x}) is still in use, trying again later.", hashes::find(hash), hash.0);
+                    }
+                },
+                Err(e) => {
+                    warn!("Failed to reload '{}' ({:#x}): {}", hashes::find(hash), hash.0, e);
+                    self.retry.remove(&hash);
+                },
+            }
+        }
+    }
+}
+
+/// Starts a background thread which periodically checks the enabled mods for changes and queues them to be reloaded.
+/// Must be called after the filesystem has been initialized.
+pub fn start() {
+    let result = std::thread::Builder::new().stack_size(0x40000).spawn(|| {
+        let mut watcher = Watcher::new();
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            watcher.poll();
+        }
+    });
+
+    if let Err(e) = result {
+        error!("Failed to start the file watcher. Reason: {:?}", e);
+    }
+}
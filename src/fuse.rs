@@ -1,2 +1,3 @@
 pub mod arc;
 pub mod mods;
+pub mod overlay;
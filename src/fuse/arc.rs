@@ -1,37 +1,228 @@
-use std::{io::Write, str::FromStr};
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use nn_fuse::{AccessorResult, DAccessor, DirectoryAccessor, FAccessor, FileAccessor, FileSystemAccessor, FsAccessor, FsEntryType};
+use nn_fuse::{
+    AccessorResult, DAccessor, DirectoryAccessor, DirectoryEntryType, FAccessor, FileAccessor, FileSystemAccessor, FsAccessor, FsEntryType,
+};
 use once_cell::sync::Lazy;
-use smash_arc::{ArcFile, ArcLookup, Hash40, Region};
+use smash_arc::{ArcFile, ArcLookup, Hash40, Region, SearchLookup};
 
-use crate::PathExtension;
+use crate::{hashes, resource, PathExtension};
 
 pub static ARC_FILE: Lazy<ArcFile> = Lazy::new(|| ArcFile::open("rom:/data.arc").unwrap());
 
-pub struct ArcFileAccessor(Hash40, Region);
+/// Splits a path on the arc:/ mount into the hash of the path the game knows about and the region it was requested in.
+/// File names can be written as `name+us_en.ext` to access a specific regional variant, or as a raw `0x...` hash for files
+/// with an unknown name.
+fn resolve_path(path: &Path) -> Option<(Hash40, Region)> {
+    let mut file_region = crate::config::region();
+    let mut new_path = path.display().to_string();
+
+    for region in crate::REGIONS.iter() {
+        let region_string = format!("+{}", region);
+        if new_path.contains(&region_string) {
+            new_path.remove_matches(&region_string);
+            file_region = Region::from_str(region).unwrap();
+        }
+    }
+
+    let new_path = new_path.trim_start_matches('/');
+
+    let hash = match Path::new(new_path).file_name().and_then(|name| name.to_str()) {
+        Some(name) if let Some(hex) = name.strip_prefix("0x") => Hash40(u64::from_str_radix(hex, 16).ok()?),
+        _ => Path::new(new_path).smash_hash().ok()?,
+    };
+
+    match ARC_FILE.get_file_info_from_hash(hash) {
+        Ok(info) if info.flags.is_regional() => {},
+        _ => file_region = Region::None,
+    }
+
+    Some((hash, file_region))
+}
+
+/// An open file on the arc:/ mount. Uncompressed files are read straight from the data.arc, while compressed files
+/// are decompressed once on the first read and then served from memory for the lifetime of the handle.
+pub struct ArcFileAccessor {
+    hash: Hash40,
+    region: Region,
+    raw: Option<(std::fs::File, u64)>,
+    cache: Option<Vec<u8>>,
+}
+
+impl ArcFileAccessor {
+    fn new(hash: Hash40, region: Region) -> Self {
+        let raw = match ARC_FILE.get_file_data_from_hash(hash, region) {
+            Ok(data) if data.comp_size == data.decomp_size => ARC_FILE
+                .get_file_offset_from_hash(hash, region)
+                .ok()
+                .and_then(|offset| std::fs::File::open("rom:/data.arc").ok().map(|file| (file, offset))),
+            _ => None,
+        };
+
+        Self {
+            hash,
+            region,
+            raw,
+            cache: None,
+        }
+    }
+}
 
 impl FileAccessor for ArcFileAccessor {
     fn read(&mut self, mut buffer: &mut [u8], offset: usize) -> Result<usize, AccessorResult> {
-        debug!("ArcFileAccessor::read - Buffer length: {:x}", buffer.len());
-        let file = ARC_FILE.get_file_contents(self.0, self.1).unwrap();
-        Ok(buffer.write(&file.as_slice()[offset..]).unwrap())
+        debug!("ArcFileAccessor::read - Buffer length: {:x}, Offset: {:#x}", buffer.len(), offset);
+
+        if self.raw.is_some() {
+            let size = self.get_size()?;
+            if offset >= size {
+                return Ok(0);
+            }
+
+            let (file, start) = self.raw.as_mut().unwrap();
+            let len = buffer.len().min(size - offset);
+            file.seek(SeekFrom::Start(*start + offset as u64)).map_err(|_| AccessorResult::Unexpected)?;
+            file.read_exact(&mut buffer[..len]).map_err(|_| AccessorResult::Unexpected)?;
+            return Ok(len);
+        }
+
+        if self.cache.is_none() {
+            self.cache = Some(ARC_FILE.get_file_contents(self.hash, self.region).map_err(|_| AccessorResult::Unexpected)?);
+        }
+
+        let file = self.cache.as_ref().unwrap();
+        match file.get(offset..) {
+            Some(slice) => buffer.write(slice).map_err(|_| AccessorResult::Unexpected),
+            None => Ok(0),
+        }
     }
 
     fn get_size(&mut self) -> Result<usize, AccessorResult> {
         debug!("ArcFileAccessor::get_size");
-        Ok(ARC_FILE.get_file_data_from_hash(self.0, self.1).unwrap().decomp_size as _)
+        ARC_FILE
+            .get_file_data_from_hash(self.hash, self.region)
+            .map(|data| data.decomp_size as usize)
+            .map_err(|_| AccessorResult::Unexpected)
     }
 }
 
-pub struct ArcDirAccessor;
+/// A directory on the arc:/ mount. The entries are gathered from the search section when the directory is opened,
+/// and handed out in order over however many reads the caller needs.
+pub struct ArcDirAccessor {
+    entries: Vec<(PathBuf, Option<usize>)>,
+    position: usize,
+}
+
+impl ArcDirAccessor {
+    fn new(folder: Hash40, folder_path: &Path) -> Self {
+        let search = resource::search();
+        let mut entries = Vec::new();
+
+        let mut push_entry = |path: PathBuf, hash: Hash40| {
+            if search.get_folder_path_index_from_hash(hash).is_ok() {
+                entries.push((path, None));
+                return;
+            }
+
+            let info = match ARC_FILE.get_file_info_from_hash(hash) {
+                Ok(info) => info,
+                // Files added by mods don't exist in the data.arc on the SD card
+                Err(_) => return,
+            };
+
+            if info.flags.is_regional() {
+                let region = crate::config::region();
+                if let Ok(data) = ARC_FILE.get_file_data_from_hash(hash, region) {
+                    entries.push((path.clone(), Some(data.decomp_size as usize)));
+                }
+
+                // List every regional variant as `name+region.ext` so that each one can be opened on its own
+                let stem = path.file_stem().and_then(|x| x.to_str()).unwrap_or_default().to_string();
+                let ext = path.extension().and_then(|x| x.to_str()).map(|x| format!(".{}", x)).unwrap_or_default();
+
+                for region_str in crate::REGIONS.iter() {
+                    let region = Region::from_str(region_str).unwrap();
+                    if let Ok(data) = ARC_FILE.get_file_data_from_hash(hash, region) {
+                        entries.push((
+                            path.with_file_name(format!("{}+{}{}", stem, region_str, ext)),
+                            Some(data.decomp_size as usize),
+                        ));
+                    }
+                }
+            } else if let Ok(data) = ARC_FILE.get_file_data_from_hash(hash, Region::None) {
+                entries.push((path, Some(data.decomp_size as usize)));
+            }
+        };
+
+        if folder_path.as_os_str().is_empty() {
+            // The root isn't a folder in the search section, so look for every folder without a parent instead
+            for entry in search.get_folder_path_list().iter() {
+                if entry.parent.hash40() == Hash40::from("") {
+                    let hash = entry.path.hash40();
+                    push_entry(Self::entry_path(folder_path, hash, entry.file_name.hash40()), hash);
+                }
+            }
+        } else if let Ok(entry) = search.get_folder_path_entry_from_hash(folder) {
+            let path_list_indices = search.get_path_list_indices();
+            let paths = search.get_path_list();
+
+            // The children of a folder are a linked list going through the path list indices
+            let mut index_idx = entry.get_first_child_index();
+            for _ in 0..paths.len() {
+                if index_idx == 0xFF_FFFF {
+                    break;
+                }
+
+                let path_idx = path_list_indices[index_idx] as usize;
+                if path_idx == 0xFF_FFFF {
+                    break;
+                }
+
+                let child = &paths[path_idx];
+                let hash = child.path.hash40();
+                push_entry(Self::entry_path(folder_path, hash, child.file_name.hash40()), hash);
+
+                index_idx = child.path.index() as usize;
+            }
+        }
+
+        Self { entries, position: 0 }
+    }
+
+    /// Gets the path of a child entry, falling back on the hash of the path for entries we don't know the name of
+    fn entry_path(parent: &Path, path_hash: Hash40, name_hash: Hash40) -> PathBuf {
+        if let Some(path) = hashes::try_find(path_hash) {
+            PathBuf::from(path)
+        } else if let Some(name) = hashes::try_find(name_hash) {
+            parent.join(name)
+        } else {
+            parent.join(format!("{:#x}", path_hash.0))
+        }
+    }
+}
 
 impl DirectoryAccessor for ArcDirAccessor {
-    fn read(&mut self, _buffer: &mut [nn_fuse::DirectoryEntry]) -> Result<usize, AccessorResult> {
-        Err(AccessorResult::Unimplemented)
+    fn read(&mut self, buffer: &mut [nn_fuse::DirectoryEntry]) -> Result<usize, AccessorResult> {
+        let remaining = &self.entries[self.position..];
+        let count = remaining.len().min(buffer.len());
+
+        for (out, (path, size)) in buffer.iter_mut().zip(remaining.iter()) {
+            out.path = path.clone();
+            out.ty = match size {
+                Some(size) => DirectoryEntryType::File(*size as i64),
+                None => DirectoryEntryType::Directory,
+            };
+        }
+
+        self.position += count;
+        Ok(count)
     }
 
     fn get_entry_count(&mut self) -> Result<usize, AccessorResult> {
-        Err(AccessorResult::Unimplemented)
+        Ok(self.entries.len())
     }
 }
 
@@ -40,10 +231,25 @@ pub struct ArcFuse;
 impl FileSystemAccessor for ArcFuse {
     fn get_entry_type(&self, path: &std::path::Path) -> Result<FsEntryType, AccessorResult> {
         debug!("Path: {}", path.display());
-        if path.file_name().is_some() {
+
+        let local = path.to_str().map(|x| x.trim_start_matches('/')).unwrap_or_default();
+        if local.is_empty() {
+            return Ok(FsEntryType::Directory);
+        }
+
+        let (hash, _) = resolve_path(path).ok_or(AccessorResult::PathNotFound)?;
+
+        if !resource::initialized() {
+            return Err(AccessorResult::Unexpected);
+        }
+
+        // Folders have to be checked first, since folders and files share the same path hashes
+        if resource::search().get_folder_path_index_from_hash(hash).is_ok() {
+            Ok(FsEntryType::Directory)
+        } else if ARC_FILE.get_file_path_index_from_hash(hash).is_ok() {
             Ok(FsEntryType::File)
         } else {
-            Err(AccessorResult::Unimplemented)
+            Err(AccessorResult::PathNotFound)
         }
     }
 
@@ -52,29 +258,12 @@ impl FileSystemAccessor for ArcFuse {
         let write = mode >> 1 & 1;
         let append = mode >> 2 & 1;
         debug!("Path: {}, read: {}, write: {}, append: {}", path.display(), read, write, append);
-        let mut file_region = crate::config::region();
-        let mut new_path = path.display().to_string();
-        for region in crate::REGIONS.iter() {
-            if new_path.contains(region) {
-                let region_string = format!("+{}", region);
-                new_path.remove_matches(&region_string);
-                file_region = Region::from_str(region).unwrap();
-                let _path = std::path::Path::new(&new_path);
-            }
-        }
 
-        let hash = path.smash_hash().unwrap();
-        match ARC_FILE.get_file_info_from_hash(hash) {
-            Ok(info) => {
-                if !info.flags.is_regional() {
-                    file_region = Region::None;
-                }
-            },
-            Err(_) => file_region = Region::None,
-        }
+        let (hash, file_region) = resolve_path(path).ok_or(AccessorResult::PathNotFound)?;
+
         if read != 0 {
             if ARC_FILE.get_file_path_index_from_hash(hash).is_ok() {
-                Ok(FAccessor::new(ArcFileAccessor(hash, file_region), mode))
+                Ok(FAccessor::new(ArcFileAccessor::new(hash, file_region), mode))
             } else {
                 Err(AccessorResult::PathNotFound)
             }
@@ -83,8 +272,24 @@ impl FileSystemAccessor for ArcFuse {
         }
     }
 
-    fn open_directory(&self, _path: &std::path::Path, _mode: skyline::nn::fs::OpenDirectoryMode) -> Result<*mut DAccessor, AccessorResult> {
-        Err(AccessorResult::Unimplemented)
+    fn open_directory(&self, path: &std::path::Path, _mode: skyline::nn::fs::OpenDirectoryMode) -> Result<*mut DAccessor, AccessorResult> {
+        debug!("ArcFuse::open_directory - Path: {}", path.display());
+
+        // Directories are listed from the game's search section, which only exists once the game has loaded the data.arc
+        if !resource::initialized() {
+            return Err(AccessorResult::Unexpected);
+        }
+
+        let local = Path::new(path.to_str().map(|x| x.trim_start_matches('/')).unwrap_or_default());
+
+        if local.as_os_str().is_empty() {
+            return Ok(DAccessor::new(ArcDirAccessor::new(Hash40::from(""), local)));
+        }
+
+        match local.smash_hash() {
+            Ok(hash) if resource::search().get_folder_path_index_from_hash(hash).is_ok() => Ok(DAccessor::new(ArcDirAccessor::new(hash, local))),
+            _ => Err(AccessorResult::PathNotFound),
+        }
     }
 }
 
@@ -1,26 +1,79 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
 
 use nn_fuse::*;
 use orbits::FileEntryType;
 
-pub struct ModFileAccessor(PathBuf);
+/// An open file on the mods:/ mount. Files that exist on the SD card as-is are read in place, while everything else
+/// (patched or API provided files) is loaded through the orbit once and then served from memory for the lifetime of the handle.
+pub struct ModFileAccessor {
+    path: PathBuf,
+    physical: Option<std::fs::File>,
+    cache: Option<Vec<u8>>,
+}
 
 pub struct ModDirAccessor(PathBuf);
 
 pub struct ModFsAccessor;
 
+impl ModFileAccessor {
+    fn new(path: PathBuf) -> Self {
+        let fs = unsafe { &*crate::GLOBAL_FILESYSTEM.data_ptr() };
+
+        // Patch files report an actual path too, so only go to the SD card if the orbit would load the file untouched
+        let physical = match fs.get().get_patch_entry_type(&path) {
+            Ok(FileEntryType::File) if fs.get().get_virtual_entry_type(&path).is_err() => fs
+                .get()
+                .query_actual_path(&path)
+                .filter(|actual| actual.is_file())
+                .and_then(|actual| std::fs::File::open(actual).ok()),
+            _ => None,
+        };
+
+        Self {
+            path,
+            physical,
+            cache: None,
+        }
+    }
+}
+
 impl FileAccessor for ModFileAccessor {
     fn read(&mut self, mut buffer: &mut [u8], offset: usize) -> Result<usize, AccessorResult> {
-        debug!(target: "no-mod-path", "ModFileAccessor::read - Buffer length: {:#x}", buffer.len());
+        debug!(target: "no-mod-path", "ModFileAccessor::read - Buffer length: {:#x}, Offset: {:#x}", buffer.len(), offset);
+
+        if let Some(file) = self.physical.as_mut() {
+            file.seek(SeekFrom::Start(offset as u64)).map_err(|_| AccessorResult::Unexpected)?;
+
+            // Read until the buffer is full or the end of the file is reached, like the loaded path does
+            let mut total = 0;
+            while total < buffer.len() {
+                match file.read(&mut buffer[total..]) {
+                    Ok(0) => break,
+                    Ok(count) => total += count,
+                    Err(_) => return Err(AccessorResult::Unexpected),
+                }
+            }
+            return Ok(total);
+        }
 
-        let fs = unsafe { &*crate::GLOBAL_FILESYSTEM.data_ptr() };
-        let file = fs.get().load(&self.0).map_err(|_| AccessorResult::Unexpected)?;
-        buffer.write(&file.as_slice()[offset..]).map_err(|_| AccessorResult::Unexpected)
+        if self.cache.is_none() {
+            let fs = unsafe { &*crate::GLOBAL_FILESYSTEM.data_ptr() };
+            self.cache = Some(fs.get().load(&self.path).map_err(|_| AccessorResult::Unexpected)?);
+        }
+
+        let file = self.cache.as_ref().unwrap();
+        match file.get(offset..) {
+            Some(slice) => buffer.write(slice).map_err(|_| AccessorResult::Unexpected),
+            None => Ok(0),
+        }
     }
 
     fn get_size(&mut self) -> Result<usize, AccessorResult> {
         let fs = unsafe { &*crate::GLOBAL_FILESYSTEM.data_ptr() };
-        let size = fs.get().query_max_filesize(&self.0).map_or_else(|| Err(AccessorResult::Unexpected), Ok);
+        let size = fs.get().query_max_filesize(&self.path).map_or_else(|| Err(AccessorResult::Unexpected), Ok);
         if let Ok(size) = size {
             debug!(target: "no-mod-path", "ModFileAccessor::get_size - Size: {:#x}", size);
         } else {
@@ -98,7 +151,7 @@ impl FileSystemAccessor for ModFsAccessor {
         }
 
         if fs.get().contains(path) {
-            Ok(FAccessor::new(ModFileAccessor(PathBuf::from(path)), mode))
+            Ok(FAccessor::new(ModFileAccessor::new(PathBuf::from(path)), mode))
         } else {
             Err(AccessorResult::PathNotFound)
         }
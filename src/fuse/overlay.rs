@@ -0,0 +1,231 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Sender},
+};
+
+use nn_fuse::*;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{config, fs::GlobalFilesystem};
+
+/// Files written to through overlay:/, waiting for the registration thread to add them to the filesystem. The thread is
+/// started the first time a written file gets closed.
+static REGISTRATIONS: Lazy<Mutex<Sender<PathBuf>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<PathBuf>();
+
+    let result = std::thread::Builder::new().stack_size(0x10000).spawn(move || {
+        while let Ok(local) = receiver.recv() {
+            // Every file closed in the meantime is registered under the same lock
+            let mut files: Vec<PathBuf> = std::iter::once(local).chain(receiver.try_iter()).collect();
+            files.sort();
+            files.dedup();
+
+            let mut fs = crate::GLOBAL_FILESYSTEM.write();
+
+            for local in files {
+                if let Ok(full_path) = physical_path(&local) {
+                    fs.register_physical_file(&local, &full_path);
+                }
+            }
+        }
+    });
+
+    if let Err(e) = result {
+        error!("Failed to start the overlay registration thread, written files will be used after a reboot. Reason: {:?}", e);
+    }
+
+    Mutex::new(sender)
+});
+
+/// Maps a path on the overlay:/ mount to the scratch directory on the SD card. Only plain names are accepted, so that
+/// nothing outside of the scratch directory can be reached.
+fn physical_path(path: &Path) -> Result<PathBuf, AccessorResult> {
+    let local = Path::new(path.to_str().map(|x| x.trim_start_matches('/')).unwrap_or_default());
+
+    if !local.components().all(|component| matches!(component, Component::Normal(_))) {
+        warn!("Refusing to access '{}' on overlay:/, as it leaves the overlay.", path.display());
+        return Err(AccessorResult::PathNotFound);
+    }
+
+    match config::overlay_path() {
+        Ok(overlay_path) => Ok(overlay_path.join(local)),
+        Err(e) => {
+            error!("Failed to create the overlay directory. Reason: {:?}", e);
+            Err(AccessorResult::Unexpected)
+        },
+    }
+}
+
+pub struct OverlayFileAccessor {
+    local: PathBuf,
+    file: File,
+    dirty: bool,
+}
+
+pub struct OverlayDirAccessor {
+    entries: Vec<(PathBuf, Option<usize>)>,
+    position: usize,
+}
+
+pub struct OverlayFsAccessor;
+
+impl FileAccessor for OverlayFileAccessor {
+    fn read(&mut self, buffer: &mut [u8], offset: usize) -> Result<usize, AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFileAccessor::read - Buffer length: {:#x}, Offset: {:#x}", buffer.len(), offset);
+
+        self.file.seek(SeekFrom::Start(offset as u64)).map_err(|_| AccessorResult::Unexpected)?;
+
+        let mut total = 0;
+        while total < buffer.len() {
+            match self.file.read(&mut buffer[total..]) {
+                Ok(0) => break,
+                Ok(count) => total += count,
+                Err(_) => return Err(AccessorResult::Unexpected),
+            }
+        }
+        Ok(total)
+    }
+
+    fn write(&mut self, buffer: &[u8], offset: usize) -> Result<usize, AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFileAccessor::write - Buffer length: {:#x}, Offset: {:#x}", buffer.len(), offset);
+
+        self.file.seek(SeekFrom::Start(offset as u64)).map_err(|_| AccessorResult::Unexpected)?;
+        self.file.write_all(buffer).map_err(|_| AccessorResult::Unexpected)?;
+        self.dirty = true;
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> Result<(), AccessorResult> {
+        self.file.flush().map_err(|_| AccessorResult::Unexpected)
+    }
+
+    fn set_size(&mut self, size: usize) -> Result<(), AccessorResult> {
+        self.file.set_len(size as u64).map_err(|_| AccessorResult::Unexpected)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn get_size(&mut self) -> Result<usize, AccessorResult> {
+        self.file.metadata().map(|meta| meta.len() as usize).map_err(|_| AccessorResult::Unexpected)
+    }
+}
+
+impl Drop for OverlayFileAccessor {
+    fn drop(&mut self) {
+        if !self.dirty || !GlobalFilesystem::is_init() {
+            return;
+        }
+
+        let _ = self.file.flush();
+
+        // The file is closed from whatever thread the plugin is running on, which could already hold the filesystem lock
+        let _ = REGISTRATIONS.lock().send(self.local.clone());
+    }
+}
+
+impl DirectoryAccessor for OverlayDirAccessor {
+    fn read(&mut self, buffer: &mut [DirectoryEntry]) -> Result<usize, AccessorResult> {
+        let remaining = &self.entries[self.position..];
+        let count = remaining.len().min(buffer.len());
+
+        for (out, (path, size)) in buffer.iter_mut().zip(remaining.iter()) {
+            out.path = path.clone();
+            out.ty = match size {
+                Some(size) => DirectoryEntryType::File(*size as i64),
+                None => DirectoryEntryType::Directory,
+            };
+        }
+
+        self.position += count;
+        Ok(count)
+    }
+
+    fn get_entry_count(&mut self) -> Result<usize, AccessorResult> {
+        Ok(self.entries.len())
+    }
+}
+
+impl FileSystemAccessor for OverlayFsAccessor {
+    fn get_entry_type(&self, path: &Path) -> Result<FsEntryType, AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFsAccessor::get_entry_type - Path: {}", path.display());
+
+        match std::fs::metadata(physical_path(path)?) {
+            Ok(meta) if meta.is_dir() => Ok(FsEntryType::Directory),
+            Ok(_) => Ok(FsEntryType::File),
+            Err(_) => Err(AccessorResult::PathNotFound),
+        }
+    }
+
+    fn create_file(&self, path: &Path, size: usize) -> Result<(), AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFsAccessor::create_file - Path: {} | Size: {:#x}", path.display(), size);
+
+        let full_path = physical_path(path)?;
+
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|_| AccessorResult::Unexpected)?;
+        }
+
+        let file = File::create(&full_path).map_err(|_| AccessorResult::Unexpected)?;
+        file.set_len(size as u64).map_err(|_| AccessorResult::Unexpected)
+    }
+
+    fn create_directory(&self, path: &Path) -> Result<(), AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFsAccessor::create_directory - Path: {}", path.display());
+
+        std::fs::create_dir_all(physical_path(path)?).map_err(|_| AccessorResult::Unexpected)
+    }
+
+    fn delete_file(&self, path: &Path) -> Result<(), AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFsAccessor::delete_file - Path: {}", path.display());
+
+        std::fs::remove_file(physical_path(path)?).map_err(|_| AccessorResult::PathNotFound)
+    }
+
+    fn open_file(&self, path: &Path, mode: skyline::nn::fs::OpenMode) -> Result<*mut FAccessor, AccessorResult> {
+        let read = mode & 1 != 0;
+        let write = mode >> 1 & 1 != 0;
+        let append = mode >> 2 & 1 != 0;
+
+        debug!(target: "no-mod-path", "OverlayFsAccessor::open_file - Path: {} | Read: {} | Write: {} | Append: {}", path.display(), read, write, append);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(write || append)
+            .append(append)
+            .open(physical_path(path)?)
+            .map_err(|_| AccessorResult::PathNotFound)?;
+
+        let local = PathBuf::from(path.to_str().map(|x| x.trim_start_matches('/')).unwrap_or_default());
+
+        Ok(FAccessor::new(OverlayFileAccessor { local, file, dirty: false }, mode))
+    }
+
+    fn open_directory(&self, path: &Path, _mode: skyline::nn::fs::OpenDirectoryMode) -> Result<*mut DAccessor, AccessorResult> {
+        debug!(target: "no-mod-path", "OverlayFsAccessor::open_directory - Path: {}", path.display());
+
+        let local = Path::new(path.to_str().map(|x| x.trim_start_matches('/')).unwrap_or_default());
+
+        let entries = std::fs::read_dir(physical_path(path)?)
+            .map_err(|_| AccessorResult::PathNotFound)?
+            .flatten()
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                let size = if meta.is_dir() { None } else { Some(meta.len() as usize) };
+                Some((local.join(entry.file_name()), size))
+            })
+            .collect();
+
+        Ok(DAccessor::new(OverlayDirAccessor { entries, position: 0 }))
+    }
+}
+
+pub fn install_overlay_fs() {
+    let accessor = FsAccessor::new(OverlayFsAccessor);
+    unsafe {
+        nn_fuse::mount("overlay", &mut *accessor).unwrap();
+    }
+    info!("Finished mounting overlay:/");
+}
@@ -1,51 +1,300 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use once_cell::sync::Lazy;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
 use smash_arc::Hash40;
 
 static HASH_FILEPATH: &str = "sd:/ultimate/arcropolis/hashes.txt";
+static HASH_CACHE_FILEPATH: &str = "sd:/ultimate/arcropolis/hashes.bin";
 
-static HASHES: Lazy<RwLock<HashMap<Hash40, &'static str>>> = Lazy::new(|| {
-    let mut hashes = HashMap::default();
+/// Labels learned in previous sessions, one per line. They are merged into the cached table on the next boot.
+static LEARNED_HASH_FILEPATH: &str = "sd:/ultimate/arcropolis/learned_hashes.txt";
 
-    let str_path = "sd:/ultimate/arcropolis/hashes.txt";
+/// Bumped whenever the layout of `HashLabels` changes, so that outdated caches get rebuilt
+const HASH_CACHE_VERSION: u32 = 2;
 
-    let s = match fs::read_to_string(str_path) {
-        Err(e) => {
-            warn!(
-                "Failed to read '{}' for hashes. Reason: {:?}. There won't be any hash lookups in this run's logs.",
-                HASH_FILEPATH, e
-            );
-            return RwLock::new(hashes);
+/// Prebuilt table of hash labels. The hashes are sorted so that lookups are a binary search, and every label lives in a
+/// single string, with `offsets[i]..offsets[i + 1]` being the label for `hashes[i]`.
+#[derive(Deserialize, Serialize)]
+struct HashLabels {
+    version: u32,
+    /// Size of the hashes.txt the table was built from, to know when it has to be rebuilt
+    source_len: u64,
+    /// Size of the learned labels that were merged in, to know when new ones have to be merged
+    learned_len: u64,
+    hashes: Vec<u64>,
+    offsets: Vec<u32>,
+    labels: String,
+}
+
+impl HashLabels {
+    fn from_entries(mut entries: Vec<(u64, &str)>, source_len: u64, learned_len: u64) -> Self {
+        entries.sort_unstable_by_key(|(hash, _)| *hash);
+        entries.dedup_by_key(|(hash, _)| *hash);
+
+        let mut hashes = Vec::with_capacity(entries.len());
+        let mut offsets = Vec::with_capacity(entries.len() + 1);
+        let mut labels = String::with_capacity(entries.iter().map(|(_, label)| label.len()).sum());
+
+        for (hash, label) in entries {
+            hashes.push(hash);
+            offsets.push(labels.len() as u32);
+            labels.push_str(label);
+        }
+
+        offsets.push(labels.len() as u32);
+
+        Self {
+            version: HASH_CACHE_VERSION,
+            source_len,
+            learned_len,
+            hashes,
+            offsets,
+            labels,
+        }
+    }
+
+    fn get(&self, hash: Hash40) -> Option<&str> {
+        let index = self.hashes.binary_search(&hash.0).ok()?;
+        self.label(index)
+    }
+
+    fn label(&self, index: usize) -> Option<&str> {
+        self.labels.get(self.offsets[index] as usize..self.offsets[index + 1] as usize)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.hashes
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, hash)| self.label(index).map(|label| (*hash, label)))
+    }
+}
+
+fn text_entries(text: &str) -> impl Iterator<Item = (u64, &str)> {
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(|line| (Hash40::from(line).0, line))
+}
+
+fn save_labels(labels: &HashLabels) {
+    match bincode::serialize(labels) {
+        Ok(data) => {
+            if let Err(e) = fs::write(HASH_CACHE_FILEPATH, data) {
+                error!("Failed to write '{}'. Reason: {:?}", HASH_CACHE_FILEPATH, e);
+            }
+        },
+        Err(e) => error!("Failed to serialize the hash labels. Reason: {:?}", *e),
+    }
+}
+
+/// Reads the cached table if it is still up to date with hashes.txt, otherwise converts hashes.txt and caches the result.
+/// Labels learned since the table was built are merged into it.
+fn load_labels() -> Option<HashLabels> {
+    let source_len = fs::metadata(HASH_FILEPATH).ok().map(|meta| meta.len());
+    let learned = fs::read_to_string(LEARNED_HASH_FILEPATH).unwrap_or_default();
+    let learned_len = learned.len() as u64;
+
+    if let Ok(data) = fs::read(HASH_CACHE_FILEPATH) {
+        match bincode::deserialize::<HashLabels>(&data) {
+            Ok(labels) if labels.version == HASH_CACHE_VERSION && source_len.map_or(true, |len| len == labels.source_len) => {
+                if labels.learned_len == learned_len {
+                    return Some(labels);
+                }
+
+                // Only the learned labels changed, so there is no need to go through hashes.txt again
+                let merged = HashLabels::from_entries(
+                    labels.entries().chain(text_entries(&learned)).collect(),
+                    labels.source_len,
+                    learned_len,
+                );
+
+                save_labels(&merged);
+                info!("Merged the learned hash labels into '{}'.", HASH_CACHE_FILEPATH);
+
+                return Some(merged);
+            },
+            Ok(_) => info!("'{}' is outdated, rebuilding it from '{}'.", HASH_CACHE_FILEPATH, HASH_FILEPATH),
+            Err(e) => warn!("Failed to parse '{}'. Reason: {:?}. Rebuilding it from '{}'.", HASH_CACHE_FILEPATH, *e, HASH_FILEPATH),
+        }
+    }
+
+    let text = match source_len {
+        Some(_) => match fs::read_to_string(HASH_FILEPATH) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to read '{}' for hashes. Reason: {:?}.", HASH_FILEPATH, e);
+                String::new()
+            },
         },
-        Ok(s) => s,
+        None => String::new(),
     };
 
-    for hs in string_to_static_str(s).lines() {
-        hashes.insert(Hash40::from(hs), hs);
+    if text.is_empty() && learned.is_empty() {
+        info!(
+            "Neither '{}' nor '{}' could be found, there won't be any hash lookups in this run's logs.",
+            HASH_CACHE_FILEPATH, HASH_FILEPATH
+        );
+        return None;
     }
 
-    RwLock::new(hashes)
-});
+    let labels = HashLabels::from_entries(
+        text_entries(&text).chain(text_entries(&learned)).collect(),
+        source_len.unwrap_or_default(),
+        learned_len,
+    );
+
+    save_labels(&labels);
+    info!("Converted '{}' to '{}'.", HASH_FILEPATH, HASH_CACHE_FILEPATH);
+
+    Some(labels)
+}
+
+/// The labels never change once loaded, so they are leaked to hand out `&'static str` without any locking
+static HASHES: Lazy<Option<&'static HashLabels>> = Lazy::new(|| load_labels().map(|labels| &*Box::leak(Box::new(labels))));
+
+/// Labels discovered at runtime, such as the paths of files added by mods
+static ADDED_HASHES: Lazy<RwLock<HashMap<Hash40, &'static str>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Labels discovered this session that haven't been written to the learned labels yet
+static UNSAVED_HASHES: Lazy<Mutex<Vec<&'static str>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set once the labels learned while booting have been saved
+static SAVE_IMMEDIATELY: AtomicBool = AtomicBool::new(false);
 
 fn string_to_static_str(s: String) -> &'static str {
     Box::leak(s.into_boxed_str())
 }
 
 pub fn try_find(hash: Hash40) -> Option<&'static str> {
-    let hashes = HASHES.read();
-    hashes.get(&hash).copied()
+    if let Some(label) = HASHES.and_then(|labels| labels.get(hash)) {
+        return Some(label);
+    }
+
+    ADDED_HASHES.read().get(&hash).copied()
 }
 
 pub fn find(hash: Hash40) -> &'static str {
     try_find(hash).unwrap_or("Unknown")
 }
 
+/// Adds a label without saving it, returning whether it was new
+fn insert(new_hash: &str) -> bool {
+    let hash = Hash40::from(new_hash);
+
+    if HASHES.and_then(|labels| labels.get(hash)).is_some() {
+        return false;
+    }
+
+    let mut hashes = ADDED_HASHES.write();
+    if hashes.contains_key(&hash) {
+        return false;
+    }
+
+    let label = string_to_static_str(new_hash.to_string());
+    hashes.insert(hash, label);
+    UNSAVED_HASHES.lock().push(label);
+    true
+}
+
+/// Once the labels of the boot have been saved, anything learned afterwards is saved right away
+fn save_if_booted() {
+    if SAVE_IMMEDIATELY.load(Ordering::SeqCst) {
+        save_learned();
+    }
+}
+
 pub fn add<S: AsRef<str>>(new_hash: S) {
-    let new_hash = new_hash.as_ref();
-    let mut hashes = HASHES.write();
-    let _ = hashes.try_insert(Hash40::from(new_hash), string_to_static_str(new_hash.to_string()));
+    if insert(new_hash.as_ref()) {
+        save_if_booted();
+    }
+}
+
+/// Learns the paths found in a config, whether it comes from a mod or was submitted through the API
+pub fn learn_from_json(json: &str) {
+    fn walk(value: &serde_json::Value) {
+        match value {
+            serde_json::Value::String(string) => learn_path(string),
+            serde_json::Value::Array(array) => array.iter().for_each(walk),
+            serde_json::Value::Object(object) => {
+                for (key, value) in object.iter() {
+                    learn_path(key);
+                    walk(value);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn learn_path(string: &str) {
+        // Raw hashes are how configs refer to files without a known name, there is nothing to learn from them
+        if string.contains('/') && !string.starts_with("0x") {
+            insert(string);
+        }
+    }
+
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(json) {
+        walk(&value);
+        save_if_booted();
+    }
+}
+
+/// Reconstructs the paths in the search section whose own label is unknown, but whose parent folder and name are known
+pub fn learn_from_search() {
+    let search = crate::resource::search();
+
+    let mut learn = |path: Hash40, parent: Hash40, name: Hash40| {
+        if try_find(path).is_some() {
+            return;
+        }
+
+        if let (Some(parent), Some(name)) = (try_find(parent), try_find(name)) {
+            let candidate = format!("{}/{}", parent, name);
+            if Hash40::from(candidate.as_str()) == path {
+                insert(&candidate);
+            }
+        }
+    };
+
+    // Folders come before their children, so the folders learned here can help with the files below
+    for entry in search.get_folder_path_list().iter() {
+        learn(entry.path.hash40(), entry.parent.hash40(), entry.file_name.hash40());
+    }
+
+    for entry in search.get_path_list().iter() {
+        learn(entry.path.hash40(), entry.parent.hash40(), entry.file_name.hash40());
+    }
+}
+
+/// Appends the labels learned this session to the learned labels, so they can be merged into the table on the next boot.
+/// Labels learned after the first call are saved as soon as they are learned.
+pub fn save_learned() {
+    SAVE_IMMEDIATELY.store(true, Ordering::SeqCst);
+
+    let labels: Vec<&'static str> = std::mem::take(&mut *UNSAVED_HASHES.lock());
+
+    if labels.is_empty() {
+        return;
+    }
+
+    let result = OpenOptions::new().create(true).append(true).open(LEARNED_HASH_FILEPATH).and_then(|mut file| {
+        for label in labels.iter() {
+            writeln!(file, "{}", label)?;
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(_) => info!("Saved {} new hash label(s).", labels.len()),
+        Err(e) => error!("Failed to write '{}'. Reason: {:?}", LEARNED_HASH_FILEPATH, e),
+    }
 }
 
 pub fn init() {
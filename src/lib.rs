@@ -17,7 +17,6 @@ use std::{
 };
 
 use arcropolis_api::Event;
-use log::LevelFilter;
 use thiserror::Error;
 
 #[macro_use]
@@ -30,6 +29,7 @@ use skyline::{hooks::InlineCtx, libc::c_char, nn};
 mod api;
 mod chainloader;
 mod config;
+mod crash;
 mod fs;
 mod fuse;
 mod hashes;
@@ -38,10 +38,12 @@ mod menus;
 mod offsets;
 mod replacement;
 mod resource;
+mod tracer;
 #[cfg(feature = "updater")]
 mod update;
 
 use fs::GlobalFilesystem;
+use offsets::Feature;
 use smash_arc::{Hash40, Region};
 
 use crate::config::{SaveLanguageId, GLOBAL_CONFIG, REGION};
@@ -73,6 +75,12 @@ macro_rules! reg_w {
     };
 }
 
+/// Relaunches the game, which ends the session cleanly as far as crash reports are concerned
+fn relaunch() {
+    crash::end_session();
+    unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
+}
+
 /// Basic code for displaying an ARCropolis dialog error informing the user to check their logs, or enable them if they don't currently.
 fn dialog_error<S: AsRef<str>>(msg: S) {
     let is_emulator = unsafe { skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as u64 } == 0x8004000;
@@ -193,8 +201,9 @@ fn get_version_string() -> String {
 fn check_for_changelog() {
     if let Ok(changelog) = std::fs::read_to_string("sd:/ultimate/arcropolis/changelog.toml") {
         match toml::from_str(&changelog) {
-            Ok(changelog) => {
-                menus::display_update_page(&changelog);
+            Ok(entry) => {
+                menus::display_update_page(&entry);
+                menus::archive_changelog(env!("CARGO_PKG_VERSION"), &changelog);
                 std::fs::remove_file("sd:/ultimate/arcropolis/changelog.toml").unwrap();
             },
             Err(_) => {
@@ -235,7 +244,20 @@ fn initial_loading(_ctx: &InlineCtx) {
     }
     drop(filesystem);
     fuse::mods::install_mod_fs();
+    fuse::overlay::install_overlay_fs();
     api::event::send_event(Event::ModFilesystemMounted);
+
+    // Keep the labels of everything seen this session around for the next ones
+    if let Err(e) = std::thread::Builder::new().stack_size(0x40000).spawn(|| {
+        hashes::learn_from_search();
+        hashes::save_learned();
+    }) {
+        error!("Failed to start learning hash labels. Reason: {:?}", e);
+    }
+
+    if config::hot_reload_enabled() {
+        fs::watcher::start();
+    }
 }
 
 #[skyline::hook(offset = offsets::title_screen_version())]
@@ -243,6 +265,11 @@ fn change_version_string(arg: u64, string: *const c_char) {
     let original_str = unsafe { skyline::from_c_str(string) };
 
     if original_str.contains("Ver.") {
+        crash::mark_booted();
+
+        #[cfg(feature = "updater")]
+        update::confirm_boot();
+
         let new_str = format!("Smash {}\nARCropolis Ver. {}\0", original_str, env!("CARGO_PKG_VERSION"));
 
         original!()(arg, skyline::c_str(&new_str))
@@ -288,13 +315,24 @@ unsafe fn packet_send(ctx: &InlineCtx) {
     }
 }
 
-#[skyline::hook(offset = 0x35bb960, inline)]
+#[skyline::hook(offset = offsets::clear_ink(), inline)]
 unsafe fn clear_ink_patch(ctx: &mut InlineCtx) {
     let res = (*ctx.registers[24].w.as_ref() as u32) % 8;
     *ctx.registers[24].w.as_mut() = res;
 }
 
-fn get_language_id_in_savedata() -> SaveLanguageId {
+/// Offset of the language selected in the game's options, in the system savedata
+const SAVEDATA_LANGUAGE_OFFSET: u64 = 0x3c6098;
+
+fn read_language_code() -> Option<u8> {
+    let mut file = std::fs::File::open("save:/save_data/system_data.bin").ok()?;
+    file.seek(SeekFrom::Start(SAVEDATA_LANGUAGE_OFFSET)).ok()?;
+    let mut language_code = [0u8];
+    file.read_exact(&mut language_code).ok()?;
+    Some(language_code[0])
+}
+
+fn get_language_id_in_savedata() -> Option<SaveLanguageId> {
     let mut uid = nn::account::Uid { id: [0; 2] };
     let mut handle = skyline_config::UserHandle::new();
 
@@ -302,7 +340,8 @@ fn get_language_id_in_savedata() -> SaveLanguageId {
         // This provides a UserHandle and sets the User in a Open state to be used.
         // TODO: Move this from skyline-config to nnsdk-rs ASAP
         if !skyline_config::open_preselected_user(&mut handle) {
-            panic!("OpenPreselectedUser returned false");
+            warn!("OpenPreselectedUser returned false, the language could not be read from the savedata.");
+            return None;
         }
 
         // Obtain the UID for this user
@@ -311,11 +350,7 @@ fn get_language_id_in_savedata() -> SaveLanguageId {
 
         nn::fs::MountSaveData(skyline::c_str("save\0"), &uid as *const nn::account::Uid as u64);
 
-        let mut file = std::fs::File::open("save:/save_data/system_data.bin").unwrap();
-        file.seek(SeekFrom::Start(0x3c6098)).unwrap();
-        let mut language_code = [0u8];
-        file.read(&mut language_code).unwrap();
-        drop(file);
+        let language_code = read_language_code();
 
         nn::fs::Unmount(skyline::c_str("save\0"));
 
@@ -324,26 +359,25 @@ fn get_language_id_in_savedata() -> SaveLanguageId {
         // TODO: Move this from skyline-config to nnsdk-rs ASAP
         skyline_config::close_user(&handle);
 
-        SaveLanguageId::from(language_code[0])
+        language_code.map(SaveLanguageId::from)
     }
 }
 
 fn get_system_region_from_language_id(language: SaveLanguageId) -> Region {
-    let system_locale_id = unsafe { *(skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as *const u8).add(0x523b00c) };
-
-    println!("system locale id: {}", system_locale_id);
-
-    let system_region_map = unsafe {
-        std::slice::from_raw_parts(
-            (skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as *const u32).add(0x4740f90 / 4),
-            14,
-        )
+    let text = unsafe { skyline::hooks::getRegionAddress(skyline::hooks::Region::Text) as *const u8 };
+
+    // The system region map has 1 for the US. Assume the US if the offsets are missing or the locale id is out of bounds,
+    // since that's what ARCropolis used to default to
+    let system_region = match (offsets::system_locale_id(), offsets::system_region_map()) {
+        (Some(locale_id_offset), Some(region_map_offset)) => {
+            let system_locale_id = unsafe { *text.add(locale_id_offset) };
+            let system_region_map =
+                unsafe { std::slice::from_raw_parts(text.add(region_map_offset) as *const u32, offsets::SYSTEM_REGION_MAP_LEN) };
+            system_region_map.get(system_locale_id as usize).copied().unwrap_or(1)
+        },
+        _ => 1,
     };
 
-    dbg!(system_region_map);
-
-    let system_region = system_region_map[system_locale_id as usize];
-
     match language {
         SaveLanguageId::Japanese => Region::Japanese,
         SaveLanguageId::English => {
@@ -387,13 +421,6 @@ pub fn main() {
     // Required to mount the savedata ourselves. It is safe to initialize multiple times.
     unsafe { nn::account::Initialize() };
 
-    // Scope to drop the lock
-    {
-        let mut region = REGION.write();
-        let language_id = get_language_id_in_savedata();
-        *region = get_system_region_from_language_id(language_id);
-    }
-
     // Force the configuration to be initialized right away, so we can be sure default files exist (hopefully)
     Lazy::force(&GLOBAL_CONFIG);
 
@@ -405,26 +432,87 @@ pub fn main() {
     }
 
     // Attempt to initialize the logger, and if we fail we will just do a regular println
-    if let Err(err) = logging::init(LevelFilter::from_str(&config::logger_level()).unwrap_or(LevelFilter::Warn)) {
+    if let Err(err) = logging::init(logging::LogFilter::from_settings(&config::settings())) {
         println!("[arcropolis] Failed to initialize logger. Reason: {:?}", err);
     }
 
+    config::log_pending_messages();
+
+    // Undo an update that broke the previous boot before anything else can go wrong
+    #[cfg(feature = "updater")]
+    update::recover();
+
+    // Look for the offsets once the logger is up, so that anything missing is reported
+    let disabled_features = offsets::report_missing();
+
+    let region_override = config::settings().region_override.as_deref().and_then(|region| Region::from_str(region).ok());
+
+    // Scope to drop the lock
+    {
+        let mut region = REGION.write();
+        *region = match region_override {
+            Some(region) => region,
+            None => get_language_id_in_savedata().map_or(Region::UsEnglish, get_system_region_from_language_id),
+        };
+    }
+
+    crash::init();
+
+    // Without the title screen hook there is no telling when the boot is over, so don't report it as unclean
+    if !offsets::is_available(Feature::VersionString) {
+        crash::mark_booted();
+
+        #[cfg(feature = "updater")]
+        update::confirm_boot();
+    }
+
+    if !disabled_features.is_empty() {
+        let features = disabled_features.iter().map(|feature| feature.description()).collect::<Vec<_>>().join("<br>");
+
+        // Give the game some time to boot before showing the dialog, like mod discovery does
+        let _ = std::thread::Builder::new().stack_size(0x40000).spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(5000));
+            dialog_error(format!(
+                "ARCropolis could not find some offsets in game version {}. The following features are disabled:<br>{}",
+                get_version_string(),
+                features
+            ));
+        });
+    }
+
+    // Compressing old logs can take a while, so don't hold the boot back for it
+    if let Err(e) = std::thread::Builder::new().stack_size(0x40000).spawn(logging::cleanup_logs) {
+        error!("Failed to start the log cleanup. Reason: {:?}", e);
+    }
+
+    if region_override.is_some() {
+        info!("Region overridden to '{}' in the configuration.", config::region_str());
+    } else {
+        info!("Using region '{}'.", config::region_str());
+    }
+
+    tracer::init();
+
+    let file_replacement = offsets::is_available(Feature::FileReplacement);
+
     // Acquire the filesystem and promise it to the initial_loading hook
     let mut filesystem = GLOBAL_FILESYSTEM.write();
 
-    let discovery = std::thread::Builder::new()
-        .stack_size(0x40000)
-        .spawn(|| {
-            unsafe {
-                let curr_thread = nn::os::GetCurrentThread();
-                nn::os::ChangeThreadPriority(curr_thread, 0);
-            }
-            std::thread::sleep(std::time::Duration::from_millis(5000));
-            fs::perform_discovery()
-        })
-        .unwrap();
+    if file_replacement {
+        let discovery = std::thread::Builder::new()
+            .stack_size(0x40000)
+            .spawn(|| {
+                unsafe {
+                    let curr_thread = nn::os::GetCurrentThread();
+                    nn::os::ChangeThreadPriority(curr_thread, 0);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(5000));
+                fs::perform_discovery()
+            })
+            .unwrap();
 
-    *filesystem = GlobalFilesystem::Promised(discovery);
+        *filesystem = GlobalFilesystem::Promised(discovery);
+    }
 
     let resources = std::thread::Builder::new()
         .stack_size(0x40000)
@@ -442,35 +530,48 @@ pub fn main() {
             .spawn(|| {
                 // Changed to pre because prerelease doesn't compile
                 if !semver::Version::from_str(env!("CARGO_PKG_VERSION")).unwrap().pre.is_empty() {
-                    update::check_for_updates(config::beta_updates(), |_update_kind| true);
+                    update::check_for_updates(&*update::default_source(), config::beta_updates(), |_, _| menus::UpdateChoice::Install);
                 }
 
                 if config::auto_update_enabled() {
-                    update::check_for_updates(config::beta_updates(), |update_kind| {
-                        // skyline_web::Dialog::yes_no(format!(
-                        //     "{} has been detected. Do you want to install it?",
-                        //     update_kind
-                        // ))
-
-                        // This didn't compile
-                        skyline_web::Dialog::no_yes(format!("{} has been detected. Do you want to install it?", update_kind))
-
-                        // match skyline_web::Dialog::yes_no(format!("{} has been detected. Do you want to install it?", update_kind)) {
-                        //     true => true,
-                        //     false => false,
-                        // }
+                    update::check_for_updates(&*update::default_source(), config::beta_updates(), |update_kind, notes| {
+                        let notes = notes.unwrap_or_else(|| menus::MainEntry {
+                            title: update_kind.to_string(),
+                            date: String::new(),
+                            description: String::from("No release notes were published for this version."),
+                            entries: Vec::new(),
+                            prompt: None,
+                        });
+
+                        menus::prompt_update_page(notes, format!("{} has been detected. Do you want to install it?", update_kind))
                     });
                 }
             })
             .unwrap();
     }
-    unsafe {
-        skyline::patching::patch_data(0x35baed4, &(0xD503201F as u32)).expect("Failed to patch inkling 1 cmp");
-        skyline::patching::patch_data(0x35baed8, &(0xD503201F as u32)).expect("Failed to patch inkling 1 b.cs");
+    let slot_spoof = offsets::is_available(Feature::SlotSpoof) && config::settings().online_slot_spoof;
+
+    if file_replacement {
+        skyline::install_hooks!(initial_loading);
+        replacement::install();
+    }
+
+    if offsets::is_available(Feature::VersionString) {
+        skyline::install_hooks!(change_version_string);
     }
 
-    skyline::install_hooks!(initial_loading, change_version_string, show_eshop, packet_send, clear_ink_patch);
-    replacement::install();
+    if offsets::is_available(Feature::EshopMenu) {
+        skyline::install_hooks!(show_eshop);
+    }
+
+    if slot_spoof {
+        unsafe {
+            skyline::patching::patch_data(offsets::ink_slot_check(), &(0xD503201F as u32)).expect("Failed to patch inkling 1 cmp");
+            skyline::patching::patch_data(offsets::ink_slot_check() + 4, &(0xD503201F as u32)).expect("Failed to patch inkling 1 b.cs");
+        }
+
+        skyline::install_hooks!(packet_send, clear_ink_patch);
+    }
 
     std::panic::set_hook(Box::new(|info| {
         let location = info.location().unwrap();
@@ -483,7 +584,13 @@ pub fn main() {
             },
         };
 
-        let err_msg = format!("thread has panicked at '{}', {}", msg, location);
+        let mut err_msg = format!("thread has panicked at '{}', {}", msg, location);
+
+        if let Some(path) = crash::write_report(&err_msg) {
+            err_msg = format!("{}\n\nA crash report was written to '{}'.", err_msg, path.display());
+            crash::end_session();
+        }
+
         skyline::error::show_error(
             69,
             "Skyline plugin as panicked! Please open the details and send a screenshot to the developer, then close the game.\n",
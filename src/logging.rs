@@ -1,19 +1,23 @@
 use std::{
+    collections::{BTreeMap, VecDeque},
     fs::File,
     io::{BufWriter, Write},
     ops::Deref,
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
     time::SystemTime,
 };
 
+use flate2::{write::GzEncoder, Compression};
 use log::{LevelFilter, Metadata, Record, SetLoggerError};
 use once_cell::sync::Lazy;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 
 use crate::config;
 
 /// Since we can't rely on most time based libraries, this is a seconds -> date/time string based on the `chrono` crates implementation
-fn format_time_string(seconds: u64) -> String {
+pub(crate) fn format_time_string(seconds: u64) -> String {
     let leapyear = |year| -> bool { year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) };
 
     static YEAR_TABLE: [[u64; 12]; 2] = [
@@ -50,10 +54,161 @@ fn format_time_string(seconds: u64) -> String {
 
 static LOG_PATH: &str = "sd:/ultimate/arcropolis/logs";
 static FILE_LOG_BUFFER: usize = 0x2000; // Room for 0x2000 characters, might have performance issues if the logger level is "Info" or "Trace"
-struct FileLogger(Option<Mutex<BufWriter<File>>>);
+
+/// Name shared by every log file of this boot, based on the time ARCropolis started logging
+static SESSION_NAME: Lazy<String> = Lazy::new(|| {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Clock may have gone backwards!");
+    format_time_string(seconds.as_secs())
+});
+
+/// The log file currently being written to. Once it reaches the maximum size, the logger rolls over to a new part.
+struct LogFile {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    part: usize,
+    written: u64,
+    max_size: u64,
+    compress: bool,
+}
+
+impl LogFile {
+    fn create(part: usize, max_size: u64, compress: bool) -> std::io::Result<Self> {
+        let name = if part == 0 {
+            format!("{}.log", *SESSION_NAME)
+        } else {
+            format!("{}.{}.log", *SESSION_NAME, part)
+        };
+
+        let path = Path::new(LOG_PATH).join(name);
+        let file = File::create(&path)?;
+
+        Ok(Self {
+            writer: BufWriter::with_capacity(FILE_LOG_BUFFER, file),
+            path,
+            part,
+            written: 0,
+            max_size,
+            compress,
+        })
+    }
+
+    fn write(&mut self, message: &[u8]) {
+        if self.max_size != 0 && self.written != 0 && self.written + message.len() as u64 > self.max_size {
+            // We are inside of the logger, so logging the error would deadlock
+            if let Err(err) = self.roll_over() {
+                println!("[arcropolis] Failed to roll the log file over. Reason: {:?}", err);
+            }
+        }
+
+        let _ = self.writer.write(message);
+        self.written += message.len() as u64;
+    }
+
+    fn roll_over(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+
+        let next = Self::create(self.part + 1, self.max_size, self.compress)?;
+        let previous = std::mem::replace(self, next);
+
+        if previous.compress {
+            let path = previous.path.clone();
+            drop(previous);
+            let _ = std::thread::Builder::new().stack_size(0x40000).spawn(move || {
+                let _ = compress_log(&path);
+            });
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Compresses a log file to `<name>.log.gz` and deletes the original
+fn compress_log(path: &Path) -> std::io::Result<()> {
+    let mut gz_name = path.as_os_str().to_os_string();
+    gz_name.push(".gz");
+
+    let mut source = File::open(path)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(File::create(&gz_name)?), Compression::default());
+    std::io::copy(&mut source, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    drop(source);
+
+    std::fs::remove_file(path)
+}
+
+/// Enforces the log retention settings on the logs of previous boots: logs past the configured count or age are deleted,
+/// and the remaining ones are compressed if requested.
+pub fn cleanup_logs() {
+    let (retention_count, retention_days, compress) = {
+        let settings = config::settings();
+        (settings.log_retention_count, settings.log_retention_days, settings.compress_old_logs)
+    };
+
+    let entries = match std::fs::read_dir(LOG_PATH) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    // Every part of a boot's log starts with the same timestamp, so group them together
+    let mut sessions: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.ends_with(".log") || name.ends_with(".log.gz") => name,
+            _ => continue,
+        };
+
+        let session = name.split('.').next().unwrap_or_default().to_string();
+
+        if session != *SESSION_NAME {
+            sessions.entry(session).or_default().push(path);
+        }
+    }
+
+    // Timestamps sort chronologically, so the same format can be used to find logs that are too old
+    let cutoff = (retention_days != 0).then(|| {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0);
+        format_time_string(now.saturating_sub(retention_days * 86400))
+    });
+
+    let (mut removed, mut compressed) = (0, 0);
+
+    // Newest first, while keeping room for the log of this boot
+    for (idx, (session, paths)) in sessions.iter().rev().enumerate() {
+        let too_many = retention_count != 0 && idx + 1 >= retention_count;
+        let too_old = cutoff.as_ref().map_or(false, |cutoff| session < cutoff);
+
+        for path in paths {
+            if too_many || too_old {
+                match std::fs::remove_file(path) {
+                    Ok(_) => removed += 1,
+                    Err(e) => warn!("Failed to delete old log '{}'. Reason: {:?}", path.display(), e),
+                }
+            } else if compress && path.extension().map_or(false, |ext| ext == "log") {
+                match compress_log(path) {
+                    Ok(_) => compressed += 1,
+                    Err(e) => warn!("Failed to compress old log '{}'. Reason: {:?}", path.display(), e),
+                }
+            }
+        }
+    }
+
+    if removed != 0 || compressed != 0 {
+        info!("Log cleanup: deleted {} and compressed {} old log file(s).", removed, compressed);
+    }
+}
+
+struct FileLogger(Option<Mutex<LogFile>>);
 
 impl Deref for FileLogger {
-    type Target = Option<Mutex<BufWriter<File>>>;
+    type Target = Option<Mutex<LogFile>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -64,19 +219,20 @@ impl FileLogger {
     pub fn write<T: AsRef<[u8]>>(&self, message: T) {
         if let Some(writer) = &self.0 {
             let mut writer = writer.lock();
-            let _ = writer.write(message.as_ref());
+            writer.write(message.as_ref());
         }
     }
 }
 
 // Summon the file logger and create a file for it based on the current time (requires time to be initialized)
 static FILE_WRITER: Lazy<FileLogger> = Lazy::new(|| {
-    let seconds = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .expect("Clock may have gone backwards!");
-    let path = Path::new(LOG_PATH).join(format!("{}.log", format_time_string(seconds.as_secs())));
+    let (max_size, compress) = {
+        let settings = config::settings();
+        (settings.log_max_size_mb * 0x10_0000, settings.compress_old_logs)
+    };
+
     let _ = std::fs::create_dir_all(LOG_PATH);
-    std::fs::File::create(path).map_or_else(
+    LogFile::create(0, max_size, compress).map_or_else(
         |_| {
             error!(target: "std", "Unable to initialize the file logger!");
             FileLogger(None)
@@ -88,23 +244,137 @@ static FILE_WRITER: Lazy<FileLogger> = Lazy::new(|| {
                 std::thread::sleep(std::time::Duration::from_millis(2000));
                 log::logger().flush();
             });
-            FileLogger(Some(Mutex::new(BufWriter::with_capacity(FILE_LOG_BUFFER, file))))
+            FileLogger(Some(Mutex::new(file)))
         },
     )
 });
 
+/// Parses an env_logger style list of directives, such as `arcropolis::fs=trace,arcropolis::replacement=warn`.
+/// A directive without a module (`info`) applies to every module that isn't matched by another directive.
+pub fn parse_directives(directives: &str) -> Result<Vec<(Option<String>, LevelFilter)>, String> {
+    directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((module, level)) => LevelFilter::from_str(level.trim())
+                .map(|level| (Some(module.trim().to_string()), level))
+                .map_err(|_| format!("'{}' is not a valid level in directive '{}'", level.trim(), directive)),
+            None => LevelFilter::from_str(directive)
+                .map(|level| (None, level))
+                .map_err(|_| format!("'{}' is neither a level nor a module=level directive", directive)),
+        })
+        .collect()
+}
+
+/// Decides which records make it to the console and to the log file
+pub struct LogFilter {
+    console: LevelFilter,
+    file: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn from_settings(settings: &config::Settings) -> Self {
+        let mut console = settings.logger_level();
+        let mut file = settings.file_logger_level();
+        let mut modules = Vec::new();
+
+        // The directives are validated along with the rest of the settings, so errors can be ignored here
+        for (module, level) in parse_directives(&settings.log_directives).unwrap_or_default() {
+            match module {
+                Some(module) => modules.push((module, level)),
+                None => {
+                    console = level;
+                    file = level;
+                },
+            }
+        }
+
+        // The most specific directive has to be found first
+        modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+
+        Self { console, file, modules }
+    }
+
+    fn module_level(&self, module_path: &str) -> Option<LevelFilter> {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                module_path == module || (module_path.starts_with(module.as_str()) && module_path[module.len()..].starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+    }
+
+    pub fn console_level(&self, module_path: &str) -> LevelFilter {
+        self.module_level(module_path).unwrap_or(self.console)
+    }
+
+    pub fn file_level(&self, module_path: &str) -> LevelFilter {
+        self.module_level(module_path).unwrap_or(self.file)
+    }
+
+    /// The most verbose level any record could be logged at, which is what the `log` macros get to filter with
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.console.max(self.file), LevelFilter::max)
+    }
+}
+
+static FILTER: Lazy<RwLock<LogFilter>> = Lazy::new(|| {
+    RwLock::new(LogFilter {
+        console: LevelFilter::Warn,
+        file: LevelFilter::Warn,
+        modules: Vec::new(),
+    })
+});
+
+/// How many of the most recent records are kept in memory for crash reports
+const RECENT_RECORDS_LEN: usize = 256;
+
+/// Records at this level or above are always kept in memory, whatever the levels of the console and the log file are
+const RECENT_RECORDS_LEVEL: LevelFilter = LevelFilter::Info;
+
+static RECENT_RECORDS: Lazy<Mutex<VecDeque<String>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(RECENT_RECORDS_LEN)));
+static RECORD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn push_recent_record(message: String) {
+    let mut records = RECENT_RECORDS.lock();
+
+    if records.len() == RECENT_RECORDS_LEN {
+        records.pop_front();
+    }
+
+    records.push_back(message);
+    RECORD_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The most recent log records, oldest first. Returns nothing rather than blocking if the records are in use.
+pub fn recent_records() -> Vec<String> {
+    RECENT_RECORDS.try_lock().map(|records| records.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// How many records have been logged so far, to tell if anything happened since the last time it was checked
+pub fn record_count() -> usize {
+    RECORD_COUNT.load(Ordering::Relaxed)
+}
+
 struct ArcLogger;
 
 static LOGGER: ArcLogger = ArcLogger;
 
-pub fn init(filter: LevelFilter) -> Result<(), SetLoggerError> {
-    log::set_logger(&LOGGER).map(|()| log::set_max_level(filter))
+pub fn init(filter: LogFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER).map(|()| set_filter(filter))
+}
+
+/// Replaces the filter used by the logger, so that changes to the configuration apply without rebooting
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level().max(RECENT_RECORDS_LEVEL));
+    *FILTER.write() = filter;
 }
 
 impl log::Log for ArcLogger {
-    // Always log what we tell it to log
-    fn enabled(&self, _metadata: &Metadata) -> bool {
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &Record) {
@@ -117,6 +387,18 @@ impl log::Log for ArcLogger {
             None => return,
         };
 
+        let (to_console, to_file) = {
+            let filter = FILTER.read();
+            (
+                record.level() <= filter.console_level(module_path),
+                record.level() <= filter.file_level(module_path) && config::file_logging_enabled(),
+            )
+        };
+
+        if !to_console && !to_file && record.level() > RECENT_RECORDS_LEVEL {
+            return;
+        }
+
         let skip_mod_path = record.target() == "no-mod-path";
 
         let message = if record.level() == LevelFilter::Debug && !skip_mod_path {
@@ -132,24 +414,32 @@ impl log::Log for ArcLogger {
             format!("{}\n", record.args())
         };
 
+        let stripped = strip_ansi_escapes::strip(&message).unwrap_or_default();
+
         // We allow two different log targets, one for specifically logging to the skyline logger and the other for specifically
         // logging to a file. If no target is mentioned (or one that doesn't exist) we log to both.
         match record.target() {
             "std" => {
-                print!("{}", message);
+                if to_console {
+                    print!("{}", message);
+                }
             },
             "file" => {
-                if config::file_logging_enabled() {
-                    FILE_WRITER.write(strip_ansi_escapes::strip(message).unwrap_or_default());
+                if to_file {
+                    FILE_WRITER.write(&stripped);
                 }
             },
             _ => {
-                print!("{}", message);
-                if config::file_logging_enabled() {
-                    FILE_WRITER.write(strip_ansi_escapes::strip(message).unwrap_or_default());
+                if to_console {
+                    print!("{}", message);
+                }
+                if to_file {
+                    FILE_WRITER.write(&stripped);
                 }
             },
         }
+
+        push_recent_record(String::from_utf8(stripped).unwrap_or_default());
     }
 
     // Only matters for writing to a file
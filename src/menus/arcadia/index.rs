@@ -0,0 +1,253 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use smash_arc::{ArcLookup, Hash40};
+
+use crate::{
+    config,
+    fs::{PATCH_EXTENSIONS, RESERVED_NAMES},
+    PathExtension,
+};
+
+#[derive(Debug, Serialize, Copy, Clone, PartialEq, Eq)]
+pub enum FileKind {
+    /// The file exists in the data.arc
+    Replace,
+    /// The file is new, and has to be registered through a config.json to be used
+    Add,
+    /// The data.arc isn't loaded yet, like when the menu is opened during boot
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    /// Hash of the path without its region, which regional and non-regional files for the same game file share
+    #[serde(skip)]
+    pub hash: Hash40,
+    pub size: u64,
+    pub kind: FileKind,
+}
+
+/// The files of a mod, sorted the same way discovery would see them
+#[derive(Debug, Default)]
+pub struct ModIndex {
+    /// Files that end up in the filesystem, and that two mods can't both provide
+    pub files: Vec<IndexedFile>,
+    /// Files that are merged into existing files, or read by ARCropolis itself
+    pub patches: Vec<PathBuf>,
+}
+
+impl ModIndex {
+    /// Walks the mod folder, skipping the files discovery would ignore: the ones at the root of the mod, the ones starting
+    /// with a period and the regional files of other regions.
+    pub fn scan(root: &Path) -> Self {
+        let regions = config::region_chain();
+        let arc_loaded = crate::resource::initialized();
+
+        let mut index = Self::default();
+
+        let walker = walkdir::WalkDir::new(root).into_iter().filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+
+            let name = entry.file_name().to_str().unwrap_or_default();
+
+            let is_out_of_region = name
+                .find('+')
+                .map_or(false, |idx| !regions.iter().any(|region| name[idx + 1..].starts_with(region.as_str())));
+
+            !name.starts_with('.') && !is_out_of_region
+        });
+
+        for entry in walker.flatten() {
+            if !entry.file_type().is_file() || entry.depth() < 2 {
+                continue;
+            }
+
+            let local = match entry.path().strip_prefix(root) {
+                Ok(local) => local.to_path_buf(),
+                Err(_) => continue,
+            };
+
+            let name = entry.file_name().to_str().unwrap_or_default();
+
+            if RESERVED_NAMES.contains(&name) || PATCH_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+                index.patches.push(local);
+                continue;
+            }
+
+            // Discovery can't hash these either, so they never get loaded
+            let Ok(hash) = local.smash_hash() else {
+                continue;
+            };
+
+            let kind = if !arc_loaded {
+                FileKind::Unknown
+            } else if crate::resource::arc().get_file_path_index_from_hash(hash).is_ok() {
+                FileKind::Replace
+            } else {
+                FileKind::Add
+            };
+
+            index.files.push(IndexedFile {
+                path: local,
+                hash,
+                size: entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                kind,
+            });
+        }
+
+        index.files.sort_by(|a, b| a.path.cmp(&b.path));
+        index.patches.sort();
+
+        index
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// The files of every mod in the menu, and which of the enabled mods provide each game file. It is kept up to date as mods
+/// get toggled, so that conflicts can be shown without going through discovery again.
+///
+/// Game files are told apart by the hash of their path without the region, like discovery does, so that a regional file
+/// conflicts with the non-regional file of another mod.
+pub struct ConflictIndex {
+    mods: Vec<ModIndex>,
+    owners: HashMap<Hash40, Vec<usize>>,
+    /// The path shown for each game file, which is the one of the first mod found providing it
+    paths: HashMap<Hash40, PathBuf>,
+    /// The mod picked in the conflict menu for a file. The file no longer counts as a conflict while that mod provides it.
+    winners: HashMap<Hash40, usize>,
+}
+
+impl ConflictIndex {
+    /// `mods` are the indexes of every mod in the menu, none of which count as enabled yet
+    pub fn new(mods: Vec<ModIndex>) -> Self {
+        Self {
+            mods,
+            owners: HashMap::new(),
+            paths: HashMap::new(),
+            winners: HashMap::new(),
+        }
+    }
+
+    pub fn set_winner(&mut self, path: &Path, winner: Option<usize>) {
+        let Ok(hash) = path.smash_hash() else {
+            return;
+        };
+
+        match winner {
+            Some(winner) => self.winners.insert(hash, winner),
+            None => self.winners.remove(&hash),
+        };
+    }
+
+    pub fn winner(&self, path: &Path) -> Option<usize> {
+        path.smash_hash().ok().and_then(|hash| self.winners.get(&hash).copied())
+    }
+
+    fn is_resolved(&self, hash: Hash40, owners: &[usize]) -> bool {
+        self.winners.get(&hash).map_or(false, |winner| owners.contains(winner))
+    }
+
+    /// The path a mod provides the same game file as `path` at, which can differ by its region
+    pub fn file_of(&self, id: usize, path: &Path) -> Option<&Path> {
+        let hash = path.smash_hash().ok()?;
+        self.mods.get(id)?.files.iter().find(|file| file.hash == hash).map(|file| file.path.as_path())
+    }
+
+    pub fn get(&self, id: usize) -> Option<&ModIndex> {
+        self.mods.get(id)
+    }
+
+    pub fn enable(&mut self, id: usize) {
+        let Some(index) = self.mods.get(id) else {
+            return;
+        };
+
+        for file in index.files.iter() {
+            let owners = self.owners.entry(file.hash).or_default();
+
+            if !owners.contains(&id) {
+                owners.push(id);
+            }
+
+            self.paths.entry(file.hash).or_insert_with(|| file.path.clone());
+        }
+    }
+
+    pub fn disable(&mut self, id: usize) {
+        let Some(index) = self.mods.get(id) else {
+            return;
+        };
+
+        for file in index.files.iter() {
+            if let Some(owners) = self.owners.get_mut(&file.hash) {
+                owners.retain(|owner| *owner != id);
+
+                if owners.is_empty() {
+                    self.owners.remove(&file.hash);
+                    self.paths.remove(&file.hash);
+                } else if self.paths.get(&file.hash) == Some(&file.path) {
+                    // Show the path of a mod that still provides the file instead
+                    let other = self.mods.get(owners[0]).and_then(|other| other.files.iter().find(|other| other.hash == file.hash));
+
+                    if let Some(other) = other {
+                        self.paths.insert(file.hash, other.path.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// The paths of a mod that enabled mods other than itself also provide, along with these mods
+    pub fn contested(&self, id: usize) -> Vec<(&Path, Vec<usize>)> {
+        let Some(index) = self.mods.get(id) else {
+            return Vec::new();
+        };
+
+        index
+            .files
+            .iter()
+            .filter_map(|file| {
+                let owners = self.owners.get(&file.hash)?;
+                let others: Vec<usize> = owners.iter().copied().filter(|owner| *owner != id).collect();
+
+                if others.is_empty() || self.is_resolved(file.hash, owners) {
+                    None
+                } else {
+                    Some((file.path.as_path(), others))
+                }
+            })
+            .collect()
+    }
+
+    /// Every enabled mod sharing at least one path with another enabled mod, without a winner picked for it
+    pub fn conflicting(&self) -> BTreeSet<usize> {
+        self.owners
+            .iter()
+            .filter(|(hash, owners)| owners.len() > 1 && !self.is_resolved(**hash, owners))
+            .flat_map(|(_, owners)| owners.iter().copied())
+            .collect()
+    }
+
+    /// Every game file provided by more than one enabled mod, resolved or not, sorted by path
+    pub fn contested_paths(&self) -> Vec<(&Path, &[usize])> {
+        let mut paths: Vec<(&Path, &[usize])> = self
+            .owners
+            .iter()
+            .filter(|(_, owners)| owners.len() > 1)
+            .filter_map(|(hash, owners)| Some((self.paths.get(hash)?.as_path(), owners.as_slice())))
+            .collect();
+
+        paths.sort_by(|a, b| a.0.cmp(b.0));
+        paths
+    }
+}
@@ -1,23 +1,35 @@
 // #![feature(proc_macro_hygiene)]
 
 use std::{
-    collections::{HashMap, HashSet},
-    path::Path,
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, SystemTime},
 };
 
 use serde::{Deserialize, Serialize};
-use skyline_web::Webpage;
+use skyline_web::{WebSession, Webpage};
 use smash_arc::Hash40;
 
 use crate::config;
 
+pub mod index;
+
+use index::{ConflictIndex, IndexedFile, ModIndex};
+
+/// Where the preview images of the mods are copied to be displayed, as `img/<id>` for the page
+const IMAGE_CACHE_PATH: &str = "sd:/atmosphere/contents/01006A800016E000/manual_html/html-document/contents.htdocs/img";
+
+/// How often the session checks on the background scan while the page has nothing to say
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Serialize)]
 pub struct Information {
-    entries: Vec<Entry>,
     workspace: String,
+    mod_count: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Entry {
     id: Option<u32>,
     folder_name: Option<String>,
@@ -29,16 +41,429 @@ pub struct Entry {
     category: Option<String>,
 }
 
+impl Entry {
+    pub fn folder_name(&self) -> &str {
+        self.folder_name.as_deref().unwrap_or_default()
+    }
+
+    /// The display name, or the folder name for mods without one
+    pub fn name(&self) -> String {
+        self.display_name.clone().or_else(|| self.folder_name.clone()).unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.is_disabled.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Enabled,
+    Disabled,
+    DateAdded,
+    Size,
+}
+
+impl Default for SortKey {
+    fn default() -> Self {
+        Self::Name
+    }
+}
+
+/// What the mod list is narrowed down and ordered by. Every field is optional on the JS side.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ModQuery {
+    search: String,
+    categories: Vec<String>,
+    author: String,
+    sort: SortKey,
+    descending: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub enum ArcadiaMessage {
     ToggleMod { id: usize, state: bool },
     ChangeAll { state: bool },
     ChangeIndexes { state: bool, indexes: Vec<usize> },
+    ChangeMatching { state: bool, query: ModQuery },
+    QueryPage { query: ModQuery, page: usize, page_size: usize },
+    GetModDetails { id: usize },
+    GetConflicts,
     DebugPrint { message: String },
     GetModSize,
     Closure,
 }
 
+/// One page of the mods matching a query, as sent to the browser
+#[derive(Debug, Serialize)]
+struct Page {
+    entries: Vec<Entry>,
+    /// Starts at 1
+    page: usize,
+    page_count: usize,
+    matching: usize,
+    mod_count: usize,
+    enabled_count: usize,
+}
+
+/// A path provided by the mod the details are about and by other enabled mods, of which only one can be loaded
+#[derive(Debug, Serialize)]
+struct Conflict {
+    path: PathBuf,
+    mods: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConflictingMod {
+    id: usize,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Details {
+    id: usize,
+    file_count: usize,
+    total_size: u64,
+    files: Vec<IndexedFile>,
+    patches: Vec<PathBuf>,
+    conflicts: Vec<Conflict>,
+    /// Every enabled mod providing at least one of the paths of this mod, so that the user can disable it
+    conflicting_mods: Vec<ConflictingMod>,
+}
+
+/// An enabled mod sharing paths with other enabled mods, as sent to the browser every time the selection changes
+#[derive(Debug, Serialize)]
+struct ModConflicts {
+    id: usize,
+    with: Vec<String>,
+    paths: Vec<PathBuf>,
+}
+
+/// A mod along with what it is sorted and filtered by
+struct ModRecord {
+    entry: Entry,
+    path: PathBuf,
+    hash: Hash40,
+    /// When ARCadia first listed the mod, in seconds since the epoch
+    added: u64,
+}
+
+/// Every mod of the workspace being edited. Only the page being looked at is ever sent to the browser, along with the
+/// preview images of the mods on it.
+struct ModList {
+    records: Vec<ModRecord>,
+    presets: HashSet<Hash40>,
+    /// Mods whose preview image was already copied to the image cache
+    previews: HashSet<usize>,
+    /// Every mod folder starts being scanned as soon as the menu opens, and the result is sent here
+    scanning: Option<Receiver<Vec<ModIndex>>>,
+    conflicts: Option<ConflictIndex>,
+    /// The winners picked in the conflict menu, which are not conflicts anymore
+    resolutions: config::ConflictResolutions,
+}
+
+fn contains_lowercase(text: Option<&str>, needle: &str) -> bool {
+    text.map_or(false, |text| text.to_lowercase().contains(needle))
+}
+
+impl ModQuery {
+    fn matches(&self, entry: &Entry) -> bool {
+        let search = self.search.trim().to_lowercase();
+        let author = self.author.trim().to_lowercase();
+
+        (self.categories.is_empty() || entry.category.as_ref().map_or(false, |category| self.categories.contains(category)))
+            && (search.is_empty()
+                || contains_lowercase(entry.display_name.as_deref(), &search)
+                || contains_lowercase(entry.folder_name.as_deref(), &search)
+                || contains_lowercase(entry.description.as_deref(), &search))
+            && (author.is_empty() || contains_lowercase(entry.authors.as_deref(), &author))
+    }
+}
+
+impl ModList {
+    /// `first_seen` holds when each mod folder was first listed, and gets the mods listed for the first time added to it
+    fn new(umm_path: &Path, presets: HashSet<Hash40>, resolutions: config::ConflictResolutions, first_seen: &mut HashMap<String, u64>) -> Self {
+        let records = get_mods(&presets)
+            .into_iter()
+            .map(|entry| {
+                let folder_name = entry.folder_name.clone().unwrap();
+                let path = umm_path.join(&folder_name);
+                let hash = Hash40::from(path.to_str().unwrap());
+
+                // A folder copied to the SD card is last modified around the time it was added, which is as close as it gets
+                // for the mods that were there before ARCadia started keeping track
+                let added = *first_seen.entry(folder_name).or_insert_with(|| {
+                    std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                        .map_or(0, |duration| duration.as_secs())
+                });
+
+                ModRecord { entry, path, hash, added }
+            })
+            .collect::<Vec<ModRecord>>();
+
+        let paths: Vec<PathBuf> = records.iter().map(|record| record.path.clone()).collect();
+        let (sender, receiver) = mpsc::channel();
+
+        let scanning = std::thread::Builder::new()
+            .stack_size(0x40000)
+            .spawn(move || {
+                // The menu might have been closed already, in which case nobody needs the result anymore
+                let _ = sender.send(paths.iter().map(|path| ModIndex::scan(path)).collect::<Vec<ModIndex>>());
+            })
+            .map_err(|e| error!("Failed to start scanning the mods in the background, they will be scanned when needed. Reason: {:?}", e))
+            .ok()
+            .map(|_| receiver);
+
+        Self {
+            records,
+            presets,
+            previews: HashSet::new(),
+            scanning,
+            conflicts: None,
+            resolutions,
+        }
+    }
+
+    fn is_enabled(&self, index: usize) -> bool {
+        self.presets.contains(&self.records[index].hash)
+    }
+
+    fn set_enabled(&mut self, index: usize, state: bool) {
+        let Some(record) = self.records.get(index) else {
+            return;
+        };
+
+        debug!("Setting {} to {}", record.path.display(), state);
+
+        if state {
+            self.presets.insert(record.hash);
+        } else {
+            self.presets.remove(&record.hash);
+        }
+
+        // Until the scan is done there is nothing to keep up to date, the enabled mods get added once it is
+        if let Some(conflicts) = self.conflicts.as_mut() {
+            if state {
+                conflicts.enable(index);
+            } else {
+                conflicts.disable(index);
+            }
+        }
+    }
+
+    fn enabled_count(&self) -> usize {
+        (0..self.records.len()).filter(|index| self.is_enabled(*index)).count()
+    }
+
+    /// Builds the conflict index out of the background scan. Unless `wait` is set, nothing is done if the scan is still
+    /// going. Returns whether the conflict index is available.
+    fn finish_scan(&mut self, wait: bool) -> bool {
+        if self.conflicts.is_none() {
+            let indexes = match self.scanning.as_ref() {
+                Some(receiver) if wait => receiver.recv().ok(),
+                Some(receiver) => match receiver.try_recv() {
+                    Ok(indexes) => Some(indexes),
+                    Err(TryRecvError::Empty) => return false,
+                    Err(TryRecvError::Disconnected) => None,
+                },
+                None => None,
+            };
+
+            // The scan couldn't be started in the background or did not make it to the end
+            let indexes = indexes.unwrap_or_else(|| self.records.iter().map(|record| ModIndex::scan(&record.path)).collect());
+            self.scanning = None;
+
+            let mut conflicts = ConflictIndex::new(indexes);
+
+            for index in (0..self.records.len()).filter(|index| self.is_enabled(*index)) {
+                conflicts.enable(index);
+            }
+
+            for (local, root) in self.resolutions.winners.iter() {
+                if let Some(winner) = self.records.iter().position(|record| record.path == Path::new(root)) {
+                    conflicts.set_winner(Path::new(local), Some(winner));
+                }
+            }
+
+            self.conflicts = Some(conflicts);
+        }
+
+        true
+    }
+
+    fn name(&self, index: usize) -> String {
+        self.records[index].entry.name()
+    }
+
+    /// Lists the files of a mod, and which of them are also provided by the enabled mods
+    fn details(&mut self, index: usize) -> Option<Details> {
+        if index >= self.records.len() {
+            return None;
+        }
+
+        // The details list the files of the mod, so they can't be sent without the scan
+        self.finish_scan(true);
+        let conflict_index = self.conflicts.as_ref().unwrap();
+        let own = conflict_index.get(index)?;
+
+        let mut conflicting_mods = BTreeSet::new();
+
+        let conflicts = conflict_index
+            .contested(index)
+            .into_iter()
+            .map(|(path, others)| {
+                conflicting_mods.extend(others.iter().copied());
+
+                Conflict {
+                    path: path.to_path_buf(),
+                    mods: others.into_iter().map(|other| self.name(other)).collect(),
+                }
+            })
+            .collect();
+
+        Some(Details {
+            id: index,
+            file_count: own.files.len(),
+            total_size: own.total_size(),
+            files: own.files.clone(),
+            patches: own.patches.clone(),
+            conflicts,
+            conflicting_mods: conflicting_mods.into_iter().map(|id| ConflictingMod { id, name: self.name(id) }).collect(),
+        })
+    }
+
+    /// Every enabled mod that can't be loaded alongside the other enabled mods as is, or `None` if the mods are still being
+    /// scanned
+    fn conflicts(&mut self) -> Option<Vec<ModConflicts>> {
+        if !self.finish_scan(false) {
+            return None;
+        }
+
+        let conflict_index = self.conflicts.as_ref().unwrap();
+
+        let conflicts = conflict_index
+            .conflicting()
+            .into_iter()
+            .map(|id| {
+                let mut with = BTreeSet::new();
+
+                let paths = conflict_index
+                    .contested(id)
+                    .into_iter()
+                    .map(|(path, others)| {
+                        with.extend(others);
+                        path.to_path_buf()
+                    })
+                    .collect();
+
+                ModConflicts {
+                    id,
+                    with: with.into_iter().map(|other| self.name(other)).collect(),
+                    paths,
+                }
+            })
+            .collect();
+
+        Some(conflicts)
+    }
+
+    fn display_name(&self, index: usize) -> String {
+        self.records[index].entry.display_name.clone().unwrap_or_default().to_lowercase()
+    }
+
+    /// Indexes of the mods matching the query, in the order they should be displayed
+    fn query(&mut self, query: &ModQuery) -> Vec<usize> {
+        let mut indexes: Vec<usize> = (0..self.records.len()).filter(|index| query.matches(&self.records[*index].entry)).collect();
+
+        match query.sort {
+            SortKey::Name => indexes.sort_by_cached_key(|index| self.display_name(*index)),
+            SortKey::Enabled => indexes.sort_by_cached_key(|index| (!self.is_enabled(*index), self.display_name(*index))),
+            SortKey::Disabled => indexes.sort_by_cached_key(|index| (self.is_enabled(*index), self.display_name(*index))),
+            SortKey::DateAdded => indexes.sort_by_cached_key(|index| (self.records[*index].added, self.display_name(*index))),
+            SortKey::Size => {
+                // The background scan already went through every file, so its result is waited for rather than walking the
+                // folders again
+                self.finish_scan(true);
+
+                let conflicts = self.conflicts.as_ref();
+                let size = |index: usize| conflicts.and_then(|conflicts| conflicts.get(index)).map_or(0, |scanned| scanned.total_size());
+                indexes.sort_by_cached_key(|index| (size(*index), self.display_name(*index)));
+            },
+        }
+
+        if query.descending {
+            indexes.reverse();
+        }
+
+        indexes
+    }
+
+    /// Copies the preview image of a mod next to the page, which only has to be done the first time it is displayed
+    fn prepare_preview(&mut self, index: usize) {
+        if !self.previews.insert(index) {
+            return;
+        }
+
+        let record = &self.records[index];
+        let preview = record.path.join("preview.webp");
+
+        if preview.exists() {
+            let target = Path::new(IMAGE_CACHE_PATH).join(record.entry.id.unwrap().to_string());
+
+            if let Err(e) = std::fs::copy(&preview, &target) {
+                error!("Failed to copy '{}' to the image cache. Reason: {:?}", preview.display(), e);
+            }
+        }
+    }
+
+    fn page(&mut self, query: &ModQuery, page: usize, page_size: usize) -> Page {
+        let indexes = self.query(query);
+        let page_size = page_size.max(1);
+        let page_count = ((indexes.len() + page_size - 1) / page_size).max(1);
+        let page = page.clamp(1, page_count);
+
+        let page_indexes: Vec<usize> = indexes.iter().skip((page - 1) * page_size).take(page_size).copied().collect();
+
+        let entries = page_indexes
+            .into_iter()
+            .map(|index| {
+                self.prepare_preview(index);
+
+                Entry {
+                    is_disabled: Some(!self.is_enabled(index)),
+                    ..self.records[index].entry.clone()
+                }
+            })
+            .collect();
+
+        Page {
+            entries,
+            page,
+            page_count,
+            matching: indexes.len(),
+            mod_count: self.records.len(),
+            enabled_count: self.enabled_count(),
+        }
+    }
+}
+
+/// Sends `reply` along with the conflicts, unless the mods are still being scanned. Returns whether they were left out.
+fn send_with_conflicts(session: &WebSession, mods: &mut ModList, mut reply: serde_json::Value) -> bool {
+    let conflicts = mods.conflicts();
+
+    if let Some(conflicts) = conflicts.as_ref() {
+        reply["conflicts"] = serde_json::json!(conflicts);
+    }
+
+    session.send(reply.to_string().as_str());
+    conflicts.is_none()
+}
+
 pub fn get_mods(presets: &HashSet<Hash40>) -> Vec<Entry> {
     let mut id: u32 = 0;
     std::fs::read_dir(&config::umm_path())
@@ -106,35 +531,32 @@ pub fn show_arcadia(workspace: Option<String>) {
     }
 
     let mut storage = config::GLOBAL_CONFIG.lock().unwrap();
-    let workspace_name: String = workspace.unwrap_or_else(|| storage.get_field("workspace").unwrap_or_else(|_| "Default".to_string()));
+    let workspace_name: String = workspace.unwrap_or_else(config::active_workspace);
     let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
     let preset_name = &workspace_list[&workspace_name];
 
     let presets: HashSet<Hash40> = storage.get_field_json(preset_name).unwrap_or_default();
-    let mut new_presets = presets.clone();
 
-    let mods: Information = Information {
-        entries: get_mods(&presets),
-        workspace: workspace_name.clone(),
-    };
+    let resolutions = config::ConflictResolutions::load(&*storage, preset_name);
 
-    // region Setup Preview Images
-    let mut images: Vec<(String, Vec<u8>)> = Vec::new();
-    for item in &mods.entries {
-        let path = &umm_path.join(item.folder_name.as_ref().unwrap()).join("preview.webp");
+    let mut first_seen: HashMap<String, u64> = storage.get_field_json("mod_first_seen").unwrap_or_default();
+    let mut mods = ModList::new(&umm_path, presets.clone(), resolutions, &mut first_seen);
 
-        if path.exists() {
-            images.push((format!("img/{}", item.id.unwrap()), std::fs::read(path).unwrap()));
-        };
+    if let Err(e) = storage.set_field_json("mod_first_seen", &first_seen) {
+        error!("Failed to remember when the mods were added. Reason: {:?}", e);
     }
 
-    let img_cache = "sd:/atmosphere/contents/01006A800016E000/manual_html/html-document/contents.htdocs/img";
+    let information = Information {
+        workspace: workspace_name.clone(),
+        mod_count: mods.records.len(),
+    };
 
-    if std::fs::metadata(&img_cache).is_ok() {
-        let _ = std::fs::remove_dir_all(&img_cache).map_err(|err| error!("Error occured in ARCadia-rs when trying to delete cache: {}", err));
+    // Preview images are copied here as pages get displayed, so start from a clean slate
+    if std::fs::metadata(IMAGE_CACHE_PATH).is_ok() {
+        let _ = std::fs::remove_dir_all(IMAGE_CACHE_PATH).map_err(|err| error!("Error occured in ARCadia-rs when trying to delete cache: {}", err));
     };
 
-    std::fs::create_dir_all(&img_cache).unwrap();
+    std::fs::create_dir_all(IMAGE_CACHE_PATH).unwrap();
 
     println!("Opening ARCadia...");
 
@@ -145,58 +567,76 @@ pub fn show_arcadia(workspace: Option<String>) {
         .file("common.js", &crate::menus::files::COMMON_JAVASCRIPT_TEXT)
         .file("arcadia.css", &crate::menus::files::ARCADIA_CSS_TEXT)
         .file("common.css", &crate::menus::files::COMMON_CSS_TEXT)
-        .file("pagination.min.js", &crate::menus::files::PAGINATION_JS)
         .file("jquery.marquee.min.js", &crate::menus::files::MARQUEE_JS)
         .file("check.svg", &crate::menus::files::CHECK_SVG)
         .file("missing.webp", &crate::menus::files::MISSING_WEBP)
-        .file("mods.json", &serde_json::to_string(&mods).unwrap())
-        .files(&images)
+        .file("mods.json", &serde_json::to_string(&information).unwrap())
         .background(skyline_web::Background::Default)
         .boot_display(skyline_web::BootDisplay::Default)
         .open_session(skyline_web::Visibility::Default)
         .unwrap();
 
-    while let Ok(message) = session.recv_json::<ArcadiaMessage>() {
+    // Replies sent while the mods were being scanned left the conflicts out, so they are pushed once the scan is done
+    let mut conflicts_pending = false;
+
+    loop {
+        if conflicts_pending {
+            if let Some(conflicts) = mods.conflicts() {
+                session.send(serde_json::json!({ "conflicts": conflicts }).to_string().as_str());
+                conflicts_pending = false;
+            }
+        }
+
+        let Some(message) = session.try_recv() else {
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+
+        let Ok(message) = serde_json::from_str::<ArcadiaMessage>(&message) else {
+            break;
+        };
+
         match message {
             ArcadiaMessage::ToggleMod { id, state } => {
-                let path = format!("{}/{}", umm_path.display(), mods.entries[id].folder_name.as_ref().unwrap());
-                let hash = Hash40::from(path.as_str());
-                debug!("Setting {} to {}", path, state);
-
-                if state {
-                    new_presets.insert(hash);
-                } else {
-                    new_presets.remove(&hash);
-                }
-
-                debug!("{} has been {}", path, state);
+                mods.set_enabled(id, state);
+                conflicts_pending |= send_with_conflicts(&session, &mut mods, serde_json::json!({}));
             },
             ArcadiaMessage::ChangeAll { state } => {
                 debug!("Changing all to {}", state);
 
-                if !state {
-                    new_presets.clear();
-                } else {
-                    for item in mods.entries.iter() {
-                        let path = format!("{}/{}", umm_path.display(), item.folder_name.as_ref().unwrap());
-                        let hash = Hash40::from(path.as_str());
-
-                        new_presets.insert(hash);
-                    }
+                for index in 0..mods.records.len() {
+                    mods.set_enabled(index, state);
                 }
+
+                let reply = serde_json::json!({ "enabled_count": mods.enabled_count() });
+                conflicts_pending |= send_with_conflicts(&session, &mut mods, reply);
             },
             ArcadiaMessage::ChangeIndexes { state, indexes } => {
                 for idx in indexes {
-                    let path = format!("{}/{}", umm_path.display(), mods.entries[idx].folder_name.as_ref().unwrap());
-                    let hash = Hash40::from(path.as_str());
-                    debug!("Setting {} to {}", path, state);
-
-                    if state {
-                        new_presets.insert(hash);
-                    } else {
-                        new_presets.remove(&hash);
-                    }
+                    mods.set_enabled(idx, state);
                 }
+
+                let reply = serde_json::json!({ "enabled_count": mods.enabled_count() });
+                conflicts_pending |= send_with_conflicts(&session, &mut mods, reply);
+            },
+            ArcadiaMessage::ChangeMatching { state, query } => {
+                for index in mods.query(&query) {
+                    mods.set_enabled(index, state);
+                }
+
+                let reply = serde_json::json!({ "enabled_count": mods.enabled_count() });
+                conflicts_pending |= send_with_conflicts(&session, &mut mods, reply);
+            },
+            ArcadiaMessage::QueryPage { query, page, page_size } => {
+                let page = mods.page(&query, page, page_size);
+                session.send(serde_json::json!({ "page": page }).to_string().as_str());
+            },
+            ArcadiaMessage::GetModDetails { id } => match mods.details(id) {
+                Some(details) => session.send(serde_json::json!({ "details": details }).to_string().as_str()),
+                None => error!("ARCadia asked for the details of mod #{}, which does not exist.", id),
+            },
+            ArcadiaMessage::GetConflicts => {
+                conflicts_pending |= send_with_conflicts(&session, &mut mods, serde_json::json!({}));
             },
             ArcadiaMessage::DebugPrint { message } => {
                 println!("session says: {}", message);
@@ -213,7 +653,9 @@ pub fn show_arcadia(workspace: Option<String>) {
         }
     }
 
-    let active_workspace: String = storage.get_field("workspace").unwrap_or_else(|_| "Default".to_string());
+    let new_presets = mods.presets;
+
+    let active_workspace: String = config::active_workspace();
 
     storage.set_field_json(&preset_name, &new_presets).unwrap();
     storage.flush();
@@ -224,7 +666,7 @@ pub fn show_arcadia(workspace: Option<String>) {
         // Acquire the filesystem so we can check if it's already finished or not (for boot-time mod manager)
         if let Some(_filesystem) = crate::GLOBAL_FILESYSTEM.try_read() {
             if active_workspace.eq(&workspace_name) && skyline_web::Dialog::yes_no("Your preset has successfully been updated!<br>Your changes will take effect on the next boot.<br>Would you like to reboot the game to reload your mods?") {
-                unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
+                crate::relaunch();
             }
         }
     }
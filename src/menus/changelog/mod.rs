@@ -1,5 +1,11 @@
+use std::path::Path;
+
+use semver::Version;
 use skyline_web::{ramhorns, Webpage};
 
+/// Where every changelog is kept once it has been shown, named after the version it is for
+pub const CHANGELOG_ARCHIVE_PATH: &str = "sd:/ultimate/arcropolis/changelogs";
+
 #[derive(ramhorns::Content, serde::Deserialize)]
 pub struct NotesEntry {
     pub section_title: String,
@@ -12,9 +18,27 @@ pub struct MainEntry {
     pub date: String,
     pub description: String,
     pub entries: Vec<NotesEntry>,
+    /// Shows the choices of the update prompt below the notes
+    #[serde(skip)]
+    pub prompt: Option<UpdatePrompt>,
 }
 
-pub fn display_update_page(info: &MainEntry) {
+#[derive(ramhorns::Content)]
+pub struct UpdatePrompt {
+    pub text: String,
+}
+
+/// What the user picked when offered an update
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UpdateChoice {
+    Install,
+    /// Don't install it now, but ask again later
+    Defer,
+    /// Never offer this version again
+    Skip,
+}
+
+fn open_notes(info: &MainEntry) -> Option<String> {
     let tpl = ramhorns::Template::new(crate::menus::files::CHANGELOG_HTML_TEXT).unwrap();
 
     let render = tpl.render(&info);
@@ -26,5 +50,79 @@ pub fn display_update_page(info: &MainEntry) {
         .background(skyline_web::Background::Default)
         .boot_display(skyline_web::BootDisplay::Default)
         .open()
-        .unwrap();
+        .ok()
+        .and_then(|response| response.get_last_url().ok().map(String::from))
+}
+
+pub fn display_update_page(info: &MainEntry) {
+    open_notes(info);
+}
+
+/// Shows the notes of an update before installing it. Closing the page without picking anything defers the update.
+pub fn prompt_update_page(mut info: MainEntry, text: String) -> UpdateChoice {
+    info.prompt = Some(UpdatePrompt { text });
+
+    match open_notes(&info).as_deref() {
+        Some("http://localhost/install") => UpdateChoice::Install,
+        Some("http://localhost/skip") => UpdateChoice::Skip,
+        _ => UpdateChoice::Defer,
+    }
+}
+
+/// Keeps a changelog around once it has been shown, so it can be read again from the main menu
+pub fn archive_changelog(version: &str, changelog: &str) {
+    let path = Path::new(CHANGELOG_ARCHIVE_PATH).join(format!("{}.toml", version));
+
+    let result = std::fs::create_dir_all(CHANGELOG_ARCHIVE_PATH).and_then(|_| std::fs::write(&path, changelog));
+
+    if let Err(e) = result {
+        warn!("Failed to archive the changelog to '{}'. Reason: {:?}", path.display(), e);
+    }
+}
+
+/// Shows every archived changelog on a single page, the most recent version first
+pub fn show_changelog_archive() {
+    let mut changelogs: Vec<(Version, MainEntry)> = std::fs::read_dir(CHANGELOG_ARCHIVE_PATH)
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let version = Version::parse(path.file_stem()?.to_str()?).ok()?;
+                    let changelog = toml::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+                    Some((version, changelog))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if changelogs.is_empty() {
+        skyline_web::DialogOk::ok("No changelogs have been archived yet. They are kept here once they have been shown after an update.");
+        return;
+    }
+
+    changelogs.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let entries = changelogs
+        .into_iter()
+        .map(|(version, changelog)| {
+            let mut contents = changelog.description;
+
+            for entry in changelog.entries {
+                contents.push_str(&format!("<br><br><u>{}</u><br>{}", entry.section_title, entry.contents));
+            }
+
+            NotesEntry {
+                section_title: format!("{} - {}", version, changelog.title),
+                contents,
+            }
+        })
+        .collect();
+
+    display_update_page(&MainEntry {
+        title: String::from("Past changelogs"),
+        date: String::new(),
+        description: String::from("The changelog of every version of ARCropolis installed on this console."),
+        entries,
+        prompt: None,
+    });
 }
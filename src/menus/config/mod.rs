@@ -1,6 +1,6 @@
 // #![feature(proc_macro_hygiene)]
 
-use log::info;
+use log::{error, info, warn};
 use serde::Deserialize;
 use skyline_config::{ConfigStorage, StorageHolder};
 use skyline_web::{Visibility, Webpage};
@@ -14,7 +14,7 @@ pub struct ConfigChanged {
 // Is this trash? Yes
 // Did I have a choice? No
 pub fn show_config_editor<CS: ConfigStorage>(storage: &mut StorageHolder<CS>) {
-    let reboot_required = false;
+    let mut reboot_required = false;
 
     let session = std::boxed::Box::new(
         Webpage::new()
@@ -33,65 +33,140 @@ pub fn show_config_editor<CS: ConfigStorage>(storage: &mut StorageHolder<CS>) {
     // Loaded
     let _ = session.recv();
 
-    if storage.get_flag("beta_updates") {
+    let mut settings = crate::config::Settings::load(storage);
+
+    if settings.beta_updates {
         session.send("beta");
     }
 
-    if storage.get_flag("legacy_discovery") {
+    if settings.legacy_discovery {
         session.send("legacy_discovery");
     }
 
-    if storage.get_flag("debug") {
+    if settings.debug {
         session.send("debug");
     }
 
-    if storage.get_flag("log_to_file") {
+    if settings.log_to_file {
         session.send("log_to_file");
     }
 
-    if storage.get_flag("auto_update") {
+    if settings.compress_old_logs {
+        session.send("compress_old_logs");
+    }
+
+    if settings.auto_update {
         session.send("auto_update");
     }
 
-    let logging: String = storage.get_field("logging_level").unwrap();
-    session.send(&logging);
+    if settings.hot_reload {
+        session.send("hot_reload");
+    }
+
+    if settings.trace_file_loads {
+        session.send("trace_file_loads");
+    }
+
+    session.send(&settings.logging_level);
+
+    session.send(&format!("file_{}", settings.file_logging_level.as_deref().unwrap_or("Same")));
+
+    session.send(&format!("region_{}", settings.region_override.as_deref().unwrap_or("auto")));
+
+    for region in settings.region_fallbacks.iter() {
+        session.send(&format!("fallback_{}", region));
+    }
 
     while let Ok(msg) = session.recv_json::<ConfigChanged>() {
         match msg.category.as_str() {
             "log" => {
-                let curr_value: String = storage.get_field("logging_level").unwrap();
-                session.send(&curr_value);
-                storage.set_field("logging_level", &msg.value).unwrap();
+                session.send(&settings.logging_level);
+                settings.logging_level = msg.value.clone();
                 session.send(&msg.value);
                 info!("Set logger to {}", &msg.value);
             },
+            "file_log" => {
+                session.send(&format!("file_{}", settings.file_logging_level.as_deref().unwrap_or("Same")));
+                settings.file_logging_level = if msg.value == "Same" { None } else { Some(msg.value.clone()) };
+                session.send(&format!("file_{}", msg.value));
+                info!("Set file logger to {}", &msg.value);
+            },
+            "log_directives" => match crate::logging::parse_directives(&msg.value) {
+                Ok(_) => {
+                    settings.log_directives = msg.value.clone();
+                    info!("Set log directives to '{}'", &msg.value);
+                },
+                Err(e) => warn!("Ignoring invalid log directives '{}'. Reason: {}", &msg.value, e),
+            },
+            "update_pin" => {
+                let version = msg.value.trim_start_matches('v');
+
+                if version.is_empty() {
+                    settings.update_pin = None;
+                    info!("Unpinned the updater");
+                } else if semver::Version::parse(version).is_ok() {
+                    settings.update_pin = Some(version.to_string());
+                    info!("Pinned the updater to {}", version);
+                } else {
+                    warn!("Ignoring invalid version '{}' to pin the updater to", &msg.value);
+                }
+            },
+            "region" => {
+                session.send(&format!("region_{}", settings.region_override.as_deref().unwrap_or("auto")));
+                let region_override = if msg.value == "auto" { None } else { Some(msg.value.clone()) };
+                reboot_required |= region_override != settings.region_override;
+                settings.region_override = region_override;
+                session.send(&format!("region_{}", msg.value));
+                info!("Set region override to {}", &msg.value);
+            },
+            "fallback" => {
+                if let Some(idx) = settings.region_fallbacks.iter().position(|region| region == &msg.value) {
+                    settings.region_fallbacks.remove(idx);
+                } else {
+                    settings.region_fallbacks.push(msg.value.clone());
+                }
+                info!("Set fallback regions to {:?}", settings.region_fallbacks);
+                reboot_required = true;
+                session.send(&format!("fallback_{}", msg.value));
+            },
             // A "true" value is passed for flags, you might be wondering why.
             // If you pass ``null``, the browser closes, because Value is not a String or a Option. I think?
             // You can change it if you feel like it, I just didn't have it within me at this point
             "beta" => {
-                let curr_value = !storage.get_flag("beta_updates");
-                storage.set_flag("beta_updates", curr_value).unwrap();
-                info!("Set beta update flag to {}", curr_value);
+                settings.beta_updates = !settings.beta_updates;
+                info!("Set beta update flag to {}", settings.beta_updates);
                 session.send("beta");
             },
             "discovery" => {
-                let curr_value = !storage.get_flag("legacy_discovery");
-                storage.set_flag("legacy_discovery", curr_value).unwrap();
-                info!("Set legacy_discovery flag to {}", curr_value);
+                settings.legacy_discovery = !settings.legacy_discovery;
+                info!("Set legacy_discovery flag to {}", settings.legacy_discovery);
                 session.send("legacy_discovery");
             },
             "log_to_file" => {
-                let curr_value = !storage.get_flag("log_to_file");
-                storage.set_flag("log_to_file", curr_value).unwrap();
-                info!("Set log_to_file flag to {}", curr_value);
+                settings.log_to_file = !settings.log_to_file;
+                info!("Set log_to_file flag to {}", settings.log_to_file);
                 session.send("log_to_file");
             },
+            "compress_old_logs" => {
+                settings.compress_old_logs = !settings.compress_old_logs;
+                info!("Set compress_old_logs flag to {}", settings.compress_old_logs);
+                session.send("compress_old_logs");
+            },
             "auto_update" => {
-                let curr_value = !storage.get_flag("auto_update");
-                storage.set_flag("auto_update", curr_value).unwrap();
-                info!("Set auto_update flag to {}", curr_value);
+                settings.auto_update = !settings.auto_update;
+                info!("Set auto_update flag to {}", settings.auto_update);
                 session.send("auto_update");
             },
+            "hot_reload" => {
+                settings.hot_reload = !settings.hot_reload;
+                info!("Set hot_reload flag to {}", settings.hot_reload);
+                session.send("hot_reload");
+            },
+            "trace_file_loads" => {
+                settings.trace_file_loads = !settings.trace_file_loads;
+                info!("Set trace_file_loads flag to {}", settings.trace_file_loads);
+                session.send("trace_file_loads");
+            },
             _ => break,
         }
     }
@@ -99,12 +174,18 @@ pub fn show_config_editor<CS: ConfigStorage>(storage: &mut StorageHolder<CS>) {
     session.exit();
     session.wait_for_exit();
 
+    if let Err(e) = settings.save(storage) {
+        error!("Failed to save the configuration. Reason: {:?}", e);
+    }
+
     storage.flush();
+    crate::config::reload_settings(storage);
+    crate::logging::set_filter(crate::logging::LogFilter::from_settings(&crate::config::settings()));
 
     if reboot_required {
         skyline_web::DialogOk::ok(
             "Some important fields in the configuration have been changed. <br>Smash will now reboot to reload ARCropolis with the new changes.",
         );
-        unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
+        crate::relaunch();
     }
 }
@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use skyline_web::Webpage;
+use smash_arc::Hash40;
+
+use crate::{
+    config,
+    menus::arcadia::{
+        get_mods,
+        index::{ConflictIndex, ModIndex},
+    },
+    PathExtension,
+};
+
+#[derive(Debug, Serialize)]
+struct ConflictMod {
+    id: usize,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ContestedPath {
+    path: PathBuf,
+    mods: Vec<usize>,
+    winner: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Information {
+    workspace: String,
+    mods: Vec<ConflictMod>,
+    paths: Vec<ContestedPath>,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum ConflictsMessage {
+    /// Picks the mod providing a path, or lets discovery handle the conflict again
+    SetWinner { path: PathBuf, id: Option<usize> },
+    /// Picks the mod providing every path both mods provide
+    SetPairWinner { mods: [usize; 2], id: Option<usize> },
+    Closure,
+}
+
+fn contested_paths(index: &ConflictIndex) -> Vec<ContestedPath> {
+    index
+        .contested_paths()
+        .into_iter()
+        .map(|(path, owners)| ContestedPath {
+            path: path.to_path_buf(),
+            mods: owners.to_vec(),
+            winner: index.winner(path),
+        })
+        .collect()
+}
+
+/// Lists every path provided by several enabled mods of a workspace, and lets the user pick which mod provides it.
+/// The choices are saved next to the preset of the workspace, for discovery to load the chosen files.
+pub fn show_conflicts(workspace: Option<String>) {
+    let umm_path = config::umm_path();
+
+    let workspace_name: String = workspace.unwrap_or_else(config::active_workspace);
+
+    // Only held while reading, as the mods still have to be scanned and the rest of ARCropolis might need the storage
+    let storage = config::GLOBAL_CONFIG.lock().unwrap();
+    let workspace_list: HashMap<String, String> = storage.get_field_json("workspace_list").unwrap_or_default();
+
+    let Some(preset_name) = workspace_list.get(&workspace_name).cloned() else {
+        drop(storage);
+        skyline_web::DialogOk::ok(format!("The workspace '{}' does not exist.", workspace_name));
+        return;
+    };
+
+    let presets: HashSet<Hash40> = storage.get_field_json(&preset_name).unwrap_or_default();
+    let mut resolutions = config::ConflictResolutions::load(&*storage, &preset_name);
+    let previous_resolutions = resolutions.clone();
+    drop(storage);
+
+    let mods: Vec<(String, PathBuf)> = get_mods(&presets)
+        .into_iter()
+        .filter(|entry| entry.is_enabled())
+        .map(|entry| (entry.name(), umm_path.join(entry.folder_name())))
+        .collect();
+
+    let mut index = ConflictIndex::new(mods.iter().map(|(_, path)| ModIndex::scan(path)).collect());
+
+    for id in 0..mods.len() {
+        index.enable(id);
+    }
+
+    for (local, root) in resolutions.winners.iter() {
+        if let Some(winner) = mods.iter().position(|(_, path)| path == Path::new(root)) {
+            index.set_winner(Path::new(local), Some(winner));
+        }
+    }
+
+    let information = Information {
+        workspace: workspace_name.clone(),
+        mods: mods.iter().enumerate().map(|(id, (name, _))| ConflictMod { id, name: name.clone() }).collect(),
+        paths: contested_paths(&index),
+    };
+
+    if information.paths.is_empty() {
+        skyline_web::DialogOk::ok("None of the enabled mods of this workspace conflict with each other.");
+        return;
+    }
+
+    let session = Webpage::new()
+        .htdocs_dir("contents")
+        .file("index.html", &crate::menus::files::CONFLICTS_HTML_TEXT)
+        .file("conflicts.css", &crate::menus::files::CONFLICTS_CSS_TEXT)
+        .file("conflicts.js", &crate::menus::files::CONFLICTS_JAVASCRIPT_TEXT)
+        .file("common.js", &crate::menus::files::COMMON_JAVASCRIPT_TEXT)
+        .file("conflicts.json", &serde_json::to_string(&information).unwrap())
+        .background(skyline_web::Background::Default)
+        .boot_display(skyline_web::BootDisplay::Default)
+        .open_session(skyline_web::Visibility::Default)
+        .unwrap();
+
+    let mut set_winner = |index: &mut ConflictIndex, path: &Path, id: Option<usize>| {
+        // The mods can provide the file under different regions, so any previous pick for the same file has to go
+        let hash = path.smash_hash().ok();
+        resolutions.winners.retain(|local, _| Path::new(local).smash_hash().ok() != hash);
+
+        match id.and_then(|id| Some((mods.get(id)?, index.file_of(id, path)?))) {
+            Some(((name, root), local)) => {
+                debug!("Picking '{}' to provide '{}'", name, local.display());
+                resolutions.winners.insert(local.to_string_lossy().to_string(), root.to_string_lossy().to_string());
+            },
+            None => debug!("Clearing the winner of '{}'", path.display()),
+        }
+
+        index.set_winner(path, id);
+    };
+
+    while let Ok(message) = session.recv_json::<ConflictsMessage>() {
+        match message {
+            ConflictsMessage::SetWinner { path, id } => {
+                set_winner(&mut index, &path, id);
+                session.send(serde_json::json!({ "paths": contested_paths(&index) }).to_string().as_str());
+            },
+            ConflictsMessage::SetPairWinner { mods: pair, id } => {
+                if let Some(id) = id.filter(|id| !pair.contains(id)) {
+                    warn!("Mod {} is not part of the pair {:?} it was picked for, ignoring it.", id, pair);
+                    session.send(serde_json::json!({ "paths": contested_paths(&index) }).to_string().as_str());
+                    continue;
+                }
+
+                let shared: Vec<(PathBuf, Option<usize>)> = index
+                    .contested_paths()
+                    .into_iter()
+                    .filter(|(_, owners)| pair.iter().all(|id| owners.contains(id)))
+                    .map(|(path, _)| (path.to_path_buf(), index.winner(path)))
+                    .collect();
+
+                for (path, winner) in shared {
+                    // Clearing a pair only clears the paths one of the two mods was picked for
+                    if id.is_some() || winner.map_or(false, |winner| pair.contains(&winner)) {
+                        set_winner(&mut index, &path, id);
+                    }
+                }
+
+                session.send(serde_json::json!({ "paths": contested_paths(&index) }).to_string().as_str());
+            },
+            ConflictsMessage::Closure => {
+                session.exit();
+                session.wait_for_exit();
+                break;
+            },
+        }
+    }
+
+    if resolutions == previous_resolutions {
+        return;
+    }
+
+    let mut storage = config::GLOBAL_CONFIG.lock().unwrap();
+
+    if let Err(e) = storage.set_field_json(config::ConflictResolutions::field_name(&preset_name), &resolutions) {
+        error!("Failed to save the conflict resolutions of workspace '{}'. Reason: {:?}", workspace_name, e);
+        return;
+    }
+
+    storage.flush();
+    drop(storage);
+
+    // Acquire the filesystem so we can check if it's already finished or not (for boot-time mod manager)
+    if crate::GLOBAL_FILESYSTEM.try_read().is_some()
+        && config::active_workspace() == workspace_name
+        && skyline_web::Dialog::yes_no("Your conflict resolutions have been saved!<br>Your changes will take effect on the next boot.<br>Would you like to reboot the game to reload your mods?")
+    {
+        crate::relaunch();
+    }
+}
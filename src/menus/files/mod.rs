@@ -21,7 +21,10 @@ pub static WORKSPACES_HTML_TEXT: &str = include_str!("../../../resources/templat
 pub static WORKSPACES_CSS_TEXT: &str = include_str!("../../../resources/css/workspaces.css");
 pub static WORKSPACES_JAVASCRIPT_TEXT: &str = include_str!("../../../resources/js/workspaces.js");
 
+pub static CONFLICTS_HTML_TEXT: &str = include_str!("../../../resources/templates/conflicts.html");
+pub static CONFLICTS_CSS_TEXT: &str = include_str!("../../../resources/css/conflicts.css");
+pub static CONFLICTS_JAVASCRIPT_TEXT: &str = include_str!("../../../resources/js/conflicts.js");
+
 pub static CHECK_SVG: &[u8] = include_bytes!("../../../resources/img/check.svg");
 pub static MARQUEE_JS: &str = include_str!("../../../resources/js/jquery.marquee.min.js");
-pub static PAGINATION_JS: &str = include_str!("../../../resources/js/pagination.min.js");
 pub static MISSING_WEBP: &[u8] = include_bytes!("../../../resources/img/missing.webp");
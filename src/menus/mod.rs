@@ -2,6 +2,8 @@ pub mod arcadia;
 pub use arcadia::*;
 pub mod workspaces;
 pub use workspaces::*;
+pub mod conflicts;
+pub use conflicts::*;
 pub mod config;
 pub use config::*;
 pub mod changelog;
@@ -9,6 +11,9 @@ pub use changelog::*;
 pub mod files;
 pub use files::*;
 use skyline_web::Webpage;
+use smash_arc::Hash40;
+
+use crate::PathExtension;
 
 pub fn show_main_menu() {
     let response = std::boxed::Box::new(
@@ -33,10 +38,93 @@ pub fn show_main_menu() {
             "http://localhost/workspaces" => {
                 show_workspaces();
             },
+            "http://localhost/conflicts" => {
+                show_conflicts(None);
+            },
             "http://localhost/config" => {
                 show_config_editor(&mut crate::config::GLOBAL_CONFIG.lock().unwrap());
             },
+            "http://localhost/changelogs" => {
+                show_changelog_archive();
+            },
+            "http://localhost/mod_updates" => {
+                #[cfg(feature = "updater")]
+                crate::update::mods::check_for_mod_updates();
+
+                #[cfg(not(feature = "updater"))]
+                skyline_web::DialogOk::ok("This build of ARCropolis was compiled without the updater.");
+            },
+            url if let Some(target) = url.strip_prefix("http://localhost/reload/") => {
+                reload_files(target);
+            },
             _ => {},
         },
     }
 }
+
+/// Debug action to mark modded files as stale. An empty target reloads every modded file.
+fn reload_files(target: &str) {
+    if !crate::config::debug_enabled() {
+        skyline_web::DialogOk::ok("Reloading files requires debug mode to be enabled in the configuration editor.");
+        return;
+    }
+
+    if !crate::fs::GlobalFilesystem::is_init() {
+        skyline_web::DialogOk::ok("Files can only be reloaded once the game has finished booting.");
+        return;
+    }
+
+    let hashes = if target.is_empty() {
+        crate::GLOBAL_FILESYSTEM.read().modded_hashes()
+    } else if let Some(hex) = target.strip_prefix("0x") {
+        match u64::from_str_radix(hex, 16) {
+            Ok(hash) => vec![Hash40(hash)],
+            Err(_) => {
+                skyline_web::DialogOk::ok(format!("'{}' is not a valid hash.", target));
+                return;
+            },
+        }
+    } else {
+        match std::path::Path::new(target).smash_hash() {
+            Ok(hash) => vec![hash],
+            Err(_) => {
+                skyline_web::DialogOk::ok(format!("'{}' is not a valid path.", target));
+                return;
+            },
+        }
+    };
+
+    let total = hashes.len();
+    let mut in_use = 0;
+    let mut failed = 0;
+
+    for hash in hashes {
+        match crate::replacement::reload::invalidate(hash) {
+            Ok(()) => {},
+            Err(crate::replacement::reload::ReloadError::InUse(count)) => {
+                debug!("'{}' ({:#x}) is still referenced {} time(s) by the game.", crate::hashes::find(hash), hash.0, count);
+                in_use += 1;
+            },
+            Err(e) => {
+                warn!("Failed to reload '{}' ({:#x}): {}", crate::hashes::find(hash), hash.0, e);
+                failed += 1;
+            },
+        }
+    }
+
+    let reloaded = total - in_use - failed;
+    let mut message = format!("{} file(s) will be reloaded the next time the game requests them.", reloaded);
+
+    if in_use > 0 {
+        message.push_str(&format!(
+            "<br>{} file(s) are still in use by the game and were left as they are. Leave the scene using them and try again.",
+            in_use
+        ));
+    }
+
+    if failed > 0 {
+        crate::dialog_error(format!("{}<br>{} file(s) could not be reloaded.", message, failed));
+    } else {
+        skyline_web::DialogOk::ok(message);
+    }
+}
@@ -10,6 +10,8 @@ use crate::config;
 pub struct Information {
     workspaces: Vec<String>,
     active_workspace: String,
+    /// The settings overridden by each workspace, formatted by `WorkspaceOverrides::to_inline`
+    overrides: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +22,7 @@ pub enum WorkspacesMessage {
     Rename { source_name: String, target_name: String },
     Remove { name: String },
     Duplicate { source_name: String, target_name: String },
+    SetOverrides { name: String, overrides: String },
     ClosureRequest,
 }
 
@@ -32,6 +35,13 @@ pub fn show_workspaces() {
     let info: Information = Information {
         workspaces: workspace_list.iter().map(|(k, _v)| k.clone()).collect(),
         active_workspace: active_workspace.clone(),
+        overrides: workspace_list
+            .iter()
+            .filter_map(|(name, preset_name)| {
+                let overrides: config::WorkspaceOverrides = storage.get_field_json(config::WorkspaceOverrides::field_name(preset_name)).ok()?;
+                Some((name.clone(), overrides.to_inline()))
+            })
+            .collect(),
     };
 
     let mut workspace_to_edit: Option<String> = None;
@@ -82,10 +92,46 @@ pub fn show_workspaces() {
 
                 let presets: HashSet<Hash40> = storage.get_field_json(source_preset_name).unwrap_or_default();
 
+                // Copy the overrides and conflict resolutions of the workspace along with its presets, if there are any
+                let overrides: Option<config::WorkspaceOverrides> =
+                    storage.get_field_json(config::WorkspaceOverrides::field_name(source_preset_name)).ok();
+                let resolutions = config::ConflictResolutions::load(&*storage, source_preset_name);
+
                 workspace_list.insert(target_name, target_preset_name.clone());
-                storage.set_field_json(target_preset_name, &presets).unwrap();
+                storage.set_field_json(&target_preset_name, &presets).unwrap();
+
+                if let Some(overrides) = overrides {
+                    storage.set_field_json(config::WorkspaceOverrides::field_name(&target_preset_name), &overrides).unwrap();
+                }
+
+                if !resolutions.winners.is_empty() {
+                    storage
+                        .set_field_json(config::ConflictResolutions::field_name(&target_preset_name), &resolutions)
+                        .unwrap();
+                }
+
                 storage.set_field_json("workspace_list", &workspace_list).unwrap_or_default();
             },
+            WorkspacesMessage::SetOverrides { name, overrides } => {
+                let preset_name = match workspace_list.get(&name) {
+                    Some(preset_name) => preset_name,
+                    None => continue,
+                };
+
+                match config::WorkspaceOverrides::from_inline(&overrides) {
+                    Ok(overrides) => {
+                        storage
+                            .set_field_json(config::WorkspaceOverrides::field_name(preset_name), &overrides)
+                            .unwrap_or_default();
+                        info!("Set the overrides of workspace '{}' to '{}'", name, overrides.to_inline());
+                        session.send(serde_json::json!({ "overrides": { "name": name, "text": overrides.to_inline() } }).to_string().as_str());
+                    },
+                    Err(e) => {
+                        warn!("Invalid overrides for workspace '{}'. Reason: {}", name, e);
+                        session.send(serde_json::json!({ "error": format!("These overrides are invalid: {}", e) }).to_string().as_str());
+                    },
+                }
+            },
             WorkspacesMessage::ClosureRequest => {
                 session.wait_for_exit();
                 session.exit();
@@ -100,6 +146,7 @@ pub fn show_workspaces() {
         storage.set_field("workspace", active_workspace.clone()).unwrap();
     }
 
+    config::reload_settings(&*storage);
     drop(storage);
 
     match workspace_to_edit {
@@ -113,7 +160,7 @@ pub fn show_workspaces() {
     if active_workspace.ne(&prev_set_workspace) {
         if let Some(_filesystem) = crate::GLOBAL_FILESYSTEM.try_read() {
             if skyline_web::Dialog::yes_no(format!("Your active workspace has successfully been changed to {}!<br>Your changes will take effect on the next boot.<br>Would you like to reboot the game to reload your mods?", active_workspace)) {
-                unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
+                crate::relaunch();
             }
         }
     }
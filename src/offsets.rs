@@ -1,24 +1,410 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use skyline::hooks::{getRegionAddress, Region};
 
+/// Set once the offsets are known, since the search for them can be what panicked
+static OFFSETS_READY: AtomicBool = AtomicBool::new(false);
+
+/// The parts of ARCropolis that rely on offsets in the game. If an offset can't be found, only the feature that needs it is disabled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Feature {
+    FileReplacement,
+    VersionString,
+    EshopMenu,
+    SlotSpoof,
+    RegionDetection,
+}
+
+impl Feature {
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::FileReplacement => "Loading mods",
+            Feature::VersionString => "Showing the ARCropolis version on the title screen",
+            Feature::EshopMenu => "Opening the ARCropolis menu from the eShop button",
+            Feature::SlotSpoof => "Spoofing costume slots online",
+            Feature::RegionDetection => "Detecting the region from the console settings",
+        }
+    }
+}
+
+/// A byte pattern to look for in the text section, written as hex bytes separated by spaces. `??` matches any byte,
+/// which is mostly useful for the displacement of branches since those tend to change between game versions.
+struct Signature {
+    pattern: &'static str,
+    /// Distance from the start of the match to the anchor of the symbol
+    adjust: isize,
+}
+
+impl Signature {
+    const fn new(pattern: &'static str, adjust: isize) -> Self {
+        Self { pattern, adjust }
+    }
+
+    fn bytes(&self) -> Vec<Option<u8>> {
+        self.pattern
+            .split_whitespace()
+            .map(|byte| if byte == "??" { None } else { u8::from_str_radix(byte, 16).ok() })
+            .collect()
+    }
+
+    /// Checks if the signature still matches at an anchor that was found previously
+    fn matches_at(&self, text: &[u8], anchor: usize) -> bool {
+        let start = match (anchor as isize).checked_sub(self.adjust) {
+            Some(start) if start >= 0 => start as usize,
+            _ => return false,
+        };
+
+        let bytes = self.bytes();
+
+        match text.get(start..start + bytes.len()) {
+            Some(window) => pattern_matches(window, &bytes),
+            None => false,
+        }
+    }
+
+    fn find(&self, text: &[u8]) -> Option<usize> {
+        let bytes = self.bytes();
+        let start = text.windows(bytes.len()).position(|window| pattern_matches(window, &bytes))?;
+        Some((start as isize + self.adjust) as usize)
+    }
+}
+
+fn pattern_matches(window: &[u8], pattern: &[Option<u8>]) -> bool {
+    window.iter().zip(pattern.iter()).all(|(byte, expected)| expected.map_or(true, |expected| *byte == expected))
+}
+
+/// How to get from the anchor of a symbol to the offset that is handed out
+enum Resolve {
+    /// The anchor is the offset
+    Direct,
+    /// The anchor is an `adrp` followed by an `ldr`, and the offset is the address they load from
+    AdrpLdr,
+}
+
+struct Symbol {
+    name: &'static str,
+    feature: Feature,
+    /// Candidates are tried in order, so the most precise ones should come first
+    signatures: &'static [Signature],
+    resolve: Resolve,
+    /// Additional check on the anchor, for symbols without a signature to check against
+    verify: Option<fn(&[u8], usize) -> bool>,
+}
+
+static SYMBOLS: &[Symbol] = &[
+    Symbol {
+        name: "lookup_stream_hash",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("29 58 40 f9 28 60 40 f9 2a 05 40 b9 09 0d 0a 8b aa 01 00 34 5f 01 00 f1", 0),
+            Signature::new("29 58 40 f9 28 60 40 f9 2a 05 40 b9 09 0d 0a 8b ?? ?? ?? 34 5f 01 00 f1", 0),
+        ],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "inflate",
+        feature: Feature::FileReplacement,
+        signatures: &[Signature::new("4b 00 1b 0b 00 01 1f d6 68 6a 40 f9 09 3d 40 f9 2c 01 40 f9", 0)],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "memcpy_1",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("f5 1f 40 b9 a7 00 00 14 e2 a3 00 91 e4 c3 00 91", -4),
+            Signature::new("f5 1f 40 b9 ?? ?? ?? 14 e2 a3 00 91 e4 c3 00 91", -4),
+        ],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "memcpy_2",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("f8 1b 40 f9 1f 03 15 eb a2 2a 00 54 96 03 18 8b 68 1a 40 f9", -4),
+            Signature::new("f8 1b 40 f9 1f 03 15 eb ?? ?? ?? 54 96 03 18 8b 68 1a 40 f9", -4),
+        ],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "memcpy_3",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("e8 03 18 aa f8 1b 40 f9 d6 02 18 8b bf 02 18 eb 88 fb ff 54", -4),
+            Signature::new("e8 03 18 aa f8 1b 40 f9 d6 02 18 8b bf 02 18 eb ?? ?? ?? 54", -4),
+        ],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "inflate_dir_file",
+        feature: Feature::FileReplacement,
+        signatures: &[Signature::new(
+            "fc 6f ba a9 fa 67 01 a9 f8 5f 02 a9 f6 57 03 a9 f4 4f 04 a9 fd 7b 05 a9 fd 43 01 91 ff 03 07 d1 4c b4 40 a9",
+            0,
+        )],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "initial_loading",
+        feature: Feature::FileReplacement,
+        signatures: &[Signature::new("08 3f 40 f9 08 01 40 f9 08 21 40 f9 08 3d 40 b9 08 5d 00 12", 0)],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "res_load_loop_start",
+        feature: Feature::FileReplacement,
+        signatures: &[Signature::new("2a 05 09 8b 6e 62 01 91 df 01 1b eb 4d f1 7d d3 ca 01 0d 8b 6d 03 0d 8b", 0)],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "res_load_loop_refresh",
+        feature: Feature::FileReplacement,
+        signatures: &[Signature::new("68 32 40 f9 ee 1b 40 f9 df 01 08 eb ec 3f 40 f9 ed 37 40 f9", 0)],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "filesystem_info",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("f3 03 00 aa 1f 01 09 6b e0 04 00 54", 12),
+            Signature::new("f3 03 00 aa 1f 01 09 6b ?? ?? ?? 54", 12),
+        ],
+        resolve: Resolve::AdrpLdr,
+        verify: None,
+    },
+    Symbol {
+        name: "res_service",
+        feature: Feature::FileReplacement,
+        signatures: &[
+            Signature::new("04 01 49 fa 21 05 00 54 5f 00 00 f9 7f 00 00 f9", 16),
+            Signature::new("04 01 49 fa ?? ?? ?? 54 5f 00 00 f9 7f 00 00 f9", 16),
+        ],
+        resolve: Resolve::AdrpLdr,
+        verify: None,
+    },
+    Symbol {
+        name: "title_screen_version",
+        feature: Feature::VersionString,
+        signatures: &[Signature::new(
+            "fc 0f 1d f8 f4 4f 01 a9 fd 7b 02 a9 fd 83 00 91 ff 07 40 d1 f4 03 01 aa f3 03 00 aa",
+            0,
+        )],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "eshop_button",
+        feature: Feature::EshopMenu,
+        signatures: &[
+            Signature::new("08 e1 43 f9 14 05 40 f9 88 22 44 39 08 04 00 35", -16),
+            Signature::new("?? ?? ?? f9 14 05 40 f9 88 22 44 39 ?? ?? ?? 35", -16),
+        ],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "packet_send",
+        feature: Feature::SlotSpoof,
+        signatures: &[Signature::new("28 4c 43 b9 08 4c 03 b9 c0 03 5f d6 00 00 00 00", 16)],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "clear_ink",
+        feature: Feature::SlotSpoof,
+        signatures: &[],
+        resolve: Resolve::Direct,
+        verify: Some(verify_clear_ink),
+    },
+    Symbol {
+        name: "ink_slot_check",
+        feature: Feature::SlotSpoof,
+        signatures: &[],
+        resolve: Resolve::Direct,
+        verify: Some(verify_ink_slot_check),
+    },
+    Symbol {
+        name: "system_locale_id",
+        feature: Feature::RegionDetection,
+        signatures: &[],
+        resolve: Resolve::Direct,
+        verify: None,
+    },
+    Symbol {
+        name: "system_region_map",
+        feature: Feature::RegionDetection,
+        signatures: &[],
+        resolve: Resolve::Direct,
+        verify: Some(verify_system_region_map),
+    },
+];
+
+/// Anchors of the symbols that can't be found with a signature, for every game version they are known for
+static KNOWN_OFFSETS: &[(&str, &[(&str, usize)])] = &[(
+    "13.0.1",
+    &[
+        ("clear_ink", 0x35bb960),
+        ("ink_slot_check", 0x35baed4),
+        ("system_locale_id", 0x523b00c),
+        ("system_region_map", 0x4740f90),
+    ],
+)];
+
+/// Length of the table mapping system locale ids to regions
+pub const SYSTEM_REGION_MAP_LEN: usize = 14;
+
+fn read_u32(text: &[u8], offset: usize) -> Option<u32> {
+    text.get(offset..offset + 4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The slot check is a `cmp` followed by a `b.cs`, which both get patched out
+fn verify_ink_slot_check(text: &[u8], anchor: usize) -> bool {
+    let is_cmp = |insn: u32| insn & 0x7f20_001f == 0x6b00_001f || insn & 0x7f80_001f == 0x7100_001f;
+    let is_b_cs = |insn: u32| insn & 0xff00_001f == 0x5400_0002;
+
+    matches!((read_u32(text, anchor), read_u32(text, anchor + 4)), (Some(cmp), Some(b_cs)) if is_cmp(cmp) && is_b_cs(b_cs))
+}
+
+/// The ink is cleared by an instruction that reads the slot from w24, which the hook takes modulo 8
+fn verify_clear_ink(text: &[u8], anchor: usize) -> bool {
+    const SLOT_REGISTER: u32 = 24;
+
+    read_u32(text, anchor).map_or(false, |insn| (insn >> 5) & 0x1f == SLOT_REGISTER || (insn >> 16) & 0x1f == SLOT_REGISTER)
+}
+
+/// Every entry of the region map is a small region id
+fn verify_system_region_map(text: &[u8], anchor: usize) -> bool {
+    (0..SYSTEM_REGION_MAP_LEN).all(|index| read_u32(text, anchor + index * 4).map_or(false, |region| region < 0x10))
+}
+
+fn is_adrp_ldr(text: &[u8], anchor: usize) -> bool {
+    match (read_u32(text, anchor), read_u32(text, anchor + 4)) {
+        (Some(adrp), Some(ldr)) => adrp & 0x9f00_0000 == 0x9000_0000 && ldr & 0x3b40_0000 == 0x3940_0000,
+        _ => false,
+    }
+}
+
+impl Symbol {
+    /// Checks that an anchor found in a previous run or in the bundled table still points to the right code
+    fn is_valid(&self, text: &[u8], anchor: usize) -> bool {
+        let matches_signature = self.signatures.is_empty() || self.signatures.iter().any(|signature| signature.matches_at(text, anchor));
+        let matches_resolve = match self.resolve {
+            Resolve::Direct => anchor < text.len(),
+            Resolve::AdrpLdr => is_adrp_ldr(text, anchor),
+        };
+
+        matches_signature && matches_resolve && self.verify.map_or(true, |verify| verify(text, anchor))
+    }
+
+    fn find(&self, text: &[u8]) -> Option<usize> {
+        self.signatures.iter().enumerate().find_map(|(index, signature)| {
+            let anchor = signature.find(text).filter(|anchor| self.is_valid(text, *anchor))?;
+
+            if index != 0 {
+                info!("Found '{}' with fallback signature #{}.", self.name, index);
+            }
+
+            Some(anchor)
+        })
+    }
+
+    fn resolve(&self, anchor: usize) -> usize {
+        match self.resolve {
+            Resolve::Direct => anchor,
+            Resolve::AdrpLdr => offset_from_adrp(anchor) + offset_from_ldr(anchor + 4),
+        }
+    }
+}
+
+/// The anchor of every symbol that could be found, cached in `offsets.toml` so the search only runs once per game version
+#[derive(Serialize, Deserialize, Default)]
+struct OffsetCache {
+    /// The game version the anchors were found for
+    #[serde(default)]
+    version: String,
+    anchors: BTreeMap<String, usize>,
+}
+
+struct Offsets {
+    resolved: BTreeMap<&'static str, usize>,
+    missing: Vec<&'static str>,
+}
+
 static OFFSETS: Lazy<Offsets> = Lazy::new(|| {
     let path = crate::CACHE_PATH.join("offsets.toml");
-    let offsets = match std::fs::read_to_string(&path) {
+    let cache: OffsetCache = match std::fs::read_to_string(&path) {
         Ok(string) => match toml::de::from_str(string.as_str()) {
-            Ok(offsets) => offsets,
+            Ok(cache) => cache,
             Err(err) => {
-                error!("Unable to parse 'offsets.toml'. Reason: {:?}", err);
-                Offsets::new()
+                warn!("Unable to parse 'offsets.toml', the offsets will be searched for again. Reason: {:?}", err);
+                OffsetCache::default()
             },
         },
         Err(err) => {
-            error!("Unable to read 'offsets.toml'. Reason: {:?}", err);
-            Offsets::new()
+            info!("Unable to read 'offsets.toml', the offsets will be searched for. Reason: {:?}", err);
+            OffsetCache::default()
         },
     };
 
-    match toml::ser::to_string_pretty(&offsets) {
+    let text = get_text();
+    let version = crate::get_version_string();
+
+    let cache = if cache.version != version {
+        info!("'offsets.toml' was made for another game version ({:?}), the offsets will be searched for again.", cache.version);
+        OffsetCache::default()
+    } else {
+        cache
+    };
+
+    let known = KNOWN_OFFSETS.iter().find(|(known_version, _)| *known_version == version).map(|(_, offsets)| *offsets);
+
+    let mut new_cache = OffsetCache { version: version.clone(), ..Default::default() };
+    let mut resolved = BTreeMap::new();
+    let mut missing = Vec::new();
+
+    for symbol in SYMBOLS.iter() {
+        // Symbols without a signature can only be trusted from the table of the running version, as a cached anchor can't be checked
+        let cached = cache.anchors.get(symbol.name).copied().filter(|_| !symbol.signatures.is_empty()).filter(|anchor| {
+            let valid = symbol.is_valid(text, *anchor);
+            if !valid {
+                warn!("The cached offset for '{}' ({:#x}) is no longer valid, searching for it again.", symbol.name, anchor);
+            }
+            valid
+        });
+
+        let anchor = cached
+            .or_else(|| {
+                known
+                    .and_then(|offsets| offsets.iter().find(|(name, _)| *name == symbol.name))
+                    .map(|(_, anchor)| *anchor)
+                    .filter(|anchor| symbol.is_valid(text, *anchor))
+            })
+            .or_else(|| symbol.find(text));
+
+        match anchor {
+            Some(anchor) => {
+                new_cache.anchors.insert(symbol.name.to_string(), anchor);
+                resolved.insert(symbol.name, symbol.resolve(anchor));
+            },
+            None => missing.push(symbol.name),
+        }
+    }
+
+    match toml::ser::to_string_pretty(&new_cache) {
         Ok(string) => {
             if std::fs::write(path, string.as_bytes()).is_err() {
                 error!("Unable to write 'offsets.toml'.")
@@ -26,75 +412,61 @@ static OFFSETS: Lazy<Offsets> = Lazy::new(|| {
         },
         Err(_) => error!("Failed to serialize offsets."),
     }
-    offsets
-});
-
-static FILESYSTEM_INFO_ADRP_SEARCH_CODE: &[u8] = &[0xf3, 0x03, 0x00, 0xaa, 0x1f, 0x01, 0x09, 0x6b, 0xe0, 0x04, 0x00, 0x54];
 
-static RES_SERVICE_ADRP_SEARCH_CODE: &[u8] = &[
-    0x04, 0x01, 0x49, 0xfa, 0x21, 0x05, 0x00, 0x54, 0x5f, 0x00, 0x00, 0xf9, 0x7f, 0x00, 0x00, 0xf9,
-];
-
-static LOOKUP_STREAM_HASH_SEARCH_CODE: &[u8] = &[
-    0x29, 0x58, 0x40, 0xf9, 0x28, 0x60, 0x40, 0xf9, 0x2a, 0x05, 0x40, 0xb9, 0x09, 0x0d, 0x0a, 0x8b, 0xaa, 0x01, 0x00, 0x34, 0x5f, 0x01, 0x00, 0xf1,
-];
-
-static TITLE_SCREEN_VERSION_SEARCH_CODE: &[u8] = &[
-    0xfc, 0x0f, 0x1d, 0xf8, 0xf4, 0x4f, 0x01, 0xa9, 0xfd, 0x7b, 0x02, 0xa9, 0xfd, 0x83, 0x00, 0x91, 0xff, 0x07, 0x40, 0xd1, 0xf4, 0x03, 0x01, 0xaa,
-    0xf3, 0x03, 0x00, 0xaa,
-];
-
-static ESHOPMANAGER_SHOW_SEARCH_CODE: &[u8] = &[
-    0x08, 0xe1, 0x43, 0xf9, 0x14, 0x05, 0x40, 0xf9, 0x88, 0x22, 0x44, 0x39, 0x08, 0x04, 0x00, 0x35,
-];
+    OFFSETS_READY.store(true, Ordering::SeqCst);
+    Offsets { resolved, missing }
+});
 
-static INFLATE_SEARCH_CODE: &[u8] = &[
-    0x4b, 0x00, 0x1b, 0x0b, 0x00, 0x01, 0x1f, 0xd6, 0x68, 0x6a, 0x40, 0xf9, 0x09, 0x3d, 0x40, 0xf9, 0x2c, 0x01, 0x40, 0xf9,
-];
+/// Whether the offsets have been resolved yet
+pub fn ready() -> bool {
+    OFFSETS_READY.load(Ordering::SeqCst)
+}
 
-static MEMCPY_1_SEARCH_CODE: &[u8] = &[
-    0xf5, 0x1f, 0x40, 0xb9, 0xa7, 0x00, 0x00, 0x14, 0xe2, 0xa3, 0x00, 0x91, 0xe4, 0xc3, 0x00, 0x91,
-];
+/// The offsets in use and the ones that couldn't be found. Returns `None` if they haven't been searched for yet.
+pub fn describe() -> Option<String> {
+    if !ready() {
+        return None;
+    }
 
-static MEMCPY_2_SEARCH_CODE: &[u8] = &[
-    0xf8, 0x1b, 0x40, 0xf9, 0x1f, 0x03, 0x15, 0xeb, 0xa2, 0x2a, 0x00, 0x54, 0x96, 0x03, 0x18, 0x8b, 0x68, 0x1a, 0x40, 0xf9,
-];
+    let mut description = String::new();
 
-static MEMCPY_3_SEARCH_CODE: &[u8] = &[
-    0xe8, 0x03, 0x18, 0xaa, 0xf8, 0x1b, 0x40, 0xf9, 0xd6, 0x02, 0x18, 0x8b, 0xbf, 0x02, 0x18, 0xeb, 0x88, 0xfb, 0xff, 0x54,
-];
-
-static INFLATE_DIR_FILE_SEARCH_CODE: &[u8] = &[
-    0xfc, 0x6f, 0xba, 0xa9, 0xfa, 0x67, 0x01, 0xa9, 0xf8, 0x5f, 0x02, 0xa9, 0xf6, 0x57, 0x03, 0xa9, 0xf4, 0x4f, 0x04, 0xa9, 0xfd, 0x7b, 0x05, 0xa9,
-    0xfd, 0x43, 0x01, 0x91, 0xff, 0x03, 0x07, 0xd1, 0x4c, 0xb4, 0x40, 0xa9,
-];
+    for (name, offset) in OFFSETS.resolved.iter() {
+        let _ = writeln!(description, "{} = {:#x}", name, offset);
+    }
 
-static MANUAL_OPEN_SEARCH_CODE: &[u8] = &[
-    0xfc, 0x4f, 0xbe, 0xa9, 0xfd, 0x7b, 0x01, 0xa9, 0xfd, 0x43, 0x00, 0x91, 0xff, 0x0f, 0x40, 0xd1,
-];
+    for name in OFFSETS.missing.iter() {
+        let _ = writeln!(description, "{} = missing", name);
+    }
 
-static INITIAL_LOADING_SEARCH_CODE: &[u8] = &[
-    0x08, 0x3f, 0x40, 0xf9, 0x08, 0x01, 0x40, 0xf9, 0x08, 0x21, 0x40, 0xf9, 0x08, 0x3d, 0x40, 0xb9, 0x08, 0x5d, 0x00, 0x12,
-];
+    Some(description)
+}
 
-static PROCESS_RESOURCE_NODE_SEARCH_CODE: &[u8] = &[
-    0x5f, 0x05, 0x00, 0x31, 0xea, 0x03, 0x8a, 0x1a, 0x29, 0x01, 0x0a, 0x0b, 0x29, 0x05, 0x00, 0x11, 0x6a, 0x3f, 0x40, 0xf9, 0x29, 0x21, 0xad, 0x9b,
-];
+/// Checks if every offset a feature relies on could be found
+pub fn is_available(feature: Feature) -> bool {
+    SYMBOLS
+        .iter()
+        .filter(|symbol| symbol.feature == feature)
+        .all(|symbol| OFFSETS.resolved.contains_key(symbol.name))
+}
 
-static RES_LOAD_LOOP_START_SEARCH_CODE: &[u8] = &[
-    0x2a, 0x05, 0x09, 0x8b, 0x6e, 0x62, 0x01, 0x91, 0xdf, 0x01, 0x1b, 0xeb, 0x4d, 0xf1, 0x7d, 0xd3, 0xca, 0x01, 0x0d, 0x8b, 0x6d, 0x03, 0x0d, 0x8b,
-];
+/// Logs every offset that couldn't be found, and returns the features that had to be disabled because of them
+pub fn report_missing() -> Vec<Feature> {
+    let mut disabled: Vec<Feature> = Vec::new();
 
-static RES_LOAD_LOOP_REFRESH_SEARCH_CODE: &[u8] = &[
-    0x68, 0x32, 0x40, 0xf9, 0xee, 0x1b, 0x40, 0xf9, 0xdf, 0x01, 0x08, 0xeb, 0xec, 0x3f, 0x40, 0xf9, 0xed, 0x37, 0x40, 0xf9,
-];
+    for symbol in SYMBOLS.iter().filter(|symbol| OFFSETS.missing.contains(&symbol.name)) {
+        error!(
+            "Unable to find the offset for '{}' in game version {}. {} will be disabled.",
+            symbol.name,
+            crate::get_version_string(),
+            symbol.feature.description()
+        );
 
-static PACKET_SEND_SEARCH_CODE: &[u8] = &[
-    0x28, 0x4c, 0x43, 0xb9, 0x08, 0x4c, 0x03, 0xb9, 0xc0, 0x03, 0x5f, 0xd6, 0x00, 0x00, 0x00, 0x00,
-];
+        if !disabled.contains(&symbol.feature) {
+            disabled.push(symbol.feature);
+        }
+    }
 
-fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
-    haystack.windows(needle.len()).position(|window| window == needle)
+    disabled
 }
 
 #[allow(clippy::inconsistent_digit_grouping)]
@@ -131,133 +503,83 @@ fn get_text() -> &'static [u8] {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct Offsets {
-    pub lookup_stream_hash: usize,
-    pub inflate: usize,
-    pub memcpy_1: usize,
-    pub memcpy_2: usize,
-    pub memcpy_3: usize,
-    pub inflate_dir_file: usize,
-    pub manual_open: usize,
-    pub initial_loading: usize,
-    pub process_resource_node: usize,
-    pub res_load_loop_start: usize,
-    pub res_load_loop_refresh: usize,
-    pub title_screen_version: usize,
-    pub eshop_button: usize,
-
-    pub filesystem_info: usize,
-    pub res_service: usize,
-
-    pub packet_send: usize,
-}
-
-impl Offsets {
-    pub fn new() -> Self {
-        let text = get_text();
-        let lookup_stream_hash = find_subsequence(text, LOOKUP_STREAM_HASH_SEARCH_CODE).expect("Unable to find subsequence");
-        let inflate = find_subsequence(text, INFLATE_SEARCH_CODE).expect("Unable to find subsequence");
-        let memcpy_1 = find_subsequence(text, MEMCPY_1_SEARCH_CODE).expect("Unable to find subsequence") - 4;
-        let memcpy_2 = find_subsequence(text, MEMCPY_2_SEARCH_CODE).expect("Unable to find subsequence") - 4;
-        let memcpy_3 = find_subsequence(text, MEMCPY_3_SEARCH_CODE).expect("Unable to find subsequence") - 4;
-        let inflate_dir_file = find_subsequence(text, INFLATE_DIR_FILE_SEARCH_CODE).expect("Unable to find subsequence");
-        let manual_open = find_subsequence(text, MANUAL_OPEN_SEARCH_CODE).expect("Unable to find subsequence");
-        let initial_loading = find_subsequence(text, INITIAL_LOADING_SEARCH_CODE).expect("Unable to find subsequence");
-        let process_resource_node = find_subsequence(text, PROCESS_RESOURCE_NODE_SEARCH_CODE).expect("Unable to find subsequence") + 0xC;
-        let res_load_loop_start = find_subsequence(text, RES_LOAD_LOOP_START_SEARCH_CODE).expect("Unable to find subsequence");
-        let res_load_loop_refresh = find_subsequence(text, RES_LOAD_LOOP_REFRESH_SEARCH_CODE).expect("Unable to find subsequence");
-        let title_screen_version = find_subsequence(text, TITLE_SCREEN_VERSION_SEARCH_CODE).expect("Unable to find subsequence!");
-        let eshop_button = find_subsequence(text, ESHOPMANAGER_SHOW_SEARCH_CODE).expect("Unable to find subsequence!") - 16;
-        let packet_send = find_subsequence(text, PACKET_SEND_SEARCH_CODE).expect("Unable to find subsequence!") + 16;
-
-        let filesystem_info = {
-            let adrp = find_subsequence(text, FILESYSTEM_INFO_ADRP_SEARCH_CODE).expect("Unable to find subsequence") + 12;
-            let adrp_offset = offset_from_adrp(adrp);
-            let ldr_offset = offset_from_ldr(adrp + 4);
-            adrp_offset + ldr_offset
-        };
-        let res_service = {
-            let adrp = find_subsequence(text, RES_SERVICE_ADRP_SEARCH_CODE).expect("Unable to find subsequence") + 16;
-            let adrp_offset = offset_from_adrp(adrp);
-            let ldr_offset = offset_from_ldr(adrp + 4);
-            adrp_offset + ldr_offset
-        };
+fn try_get(name: &str) -> Option<usize> {
+    OFFSETS.resolved.get(name).copied()
+}
 
-        Self {
-            lookup_stream_hash,
-            inflate,
-            memcpy_1,
-            memcpy_2,
-            memcpy_3,
-            inflate_dir_file,
-            manual_open,
-            initial_loading,
-            process_resource_node,
-            res_load_loop_start,
-            res_load_loop_refresh,
-            title_screen_version,
-            eshop_button,
-            packet_send,
-
-            filesystem_info,
-            res_service,
-        }
-    }
+/// Hooks are only installed for features that are available, so a missing offset is never actually used
+fn get(name: &str) -> usize {
+    try_get(name).unwrap_or_default()
 }
 
 pub fn initial_loading() -> usize {
-    OFFSETS.initial_loading
+    get("initial_loading")
 }
 
 pub fn filesystem_info() -> usize {
-    OFFSETS.filesystem_info
+    get("filesystem_info")
 }
 
 pub fn res_service() -> usize {
-    OFFSETS.res_service
+    get("res_service")
 }
 
 pub fn inflate() -> usize {
-    OFFSETS.inflate
+    get("inflate")
 }
 
 pub fn inflate_dir_file() -> usize {
-    OFFSETS.inflate_dir_file
+    get("inflate_dir_file")
 }
 
 pub fn memcpy_1() -> usize {
-    OFFSETS.memcpy_1
+    get("memcpy_1")
 }
 
 pub fn memcpy_2() -> usize {
-    OFFSETS.memcpy_2
+    get("memcpy_2")
 }
 
 pub fn memcpy_3() -> usize {
-    OFFSETS.memcpy_3
+    get("memcpy_3")
 }
 
 pub fn res_load_loop_start() -> usize {
-    OFFSETS.res_load_loop_start
+    get("res_load_loop_start")
 }
 
 pub fn res_load_loop_refresh() -> usize {
-    OFFSETS.res_load_loop_refresh
+    get("res_load_loop_refresh")
 }
 
 pub fn title_screen_version() -> usize {
-    OFFSETS.title_screen_version
+    get("title_screen_version")
 }
 
 pub fn eshop_show() -> usize {
-    OFFSETS.eshop_button
+    get("eshop_button")
 }
 
 pub fn lookup_stream_hash() -> usize {
-    OFFSETS.lookup_stream_hash
+    get("lookup_stream_hash")
 }
 
 pub fn packet_send() -> usize {
-    OFFSETS.packet_send
+    get("packet_send")
+}
+
+pub fn clear_ink() -> usize {
+    get("clear_ink")
+}
+
+pub fn ink_slot_check() -> usize {
+    get("ink_slot_check")
+}
+
+pub fn system_locale_id() -> Option<usize> {
+    try_get("system_locale_id")
+}
+
+pub fn system_region_map() -> Option<usize> {
+    try_get("system_region_map")
 }
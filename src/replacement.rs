@@ -4,6 +4,7 @@ pub mod lookup;
 pub mod addition;
 // pub mod config;
 pub mod preprocess;
+pub mod reload;
 mod stream;
 mod threads;
 mod uncompressed;
@@ -0,0 +1,102 @@
+use skyline::nn;
+use smash_arc::{ArcLookup, Hash40};
+use thiserror::Error;
+
+use crate::{
+    hashes,
+    resource::{self, LoadState},
+    GLOBAL_FILESYSTEM,
+};
+
+#[derive(Error, Debug)]
+pub enum ReloadError {
+    #[error("the filesystem is not initialized")]
+    Uninitialized,
+    #[error("the file could not be found in the data.arc")]
+    Missing,
+    #[error("the file is not modded")]
+    NotModded,
+    #[error("the loaded data is still referenced {0} time(s) by the game")]
+    InUse(u32),
+    #[error("the loaded data is shared with {0} other loaded file(s)")]
+    Shared(usize),
+}
+
+/// Clears the game's loaded flags and buffer for a file so that the next request for it goes through the resource service
+/// again. Returns `Ok(false)` if the file was not loaded to begin with, `ReloadError::InUse` if the game still holds
+/// references to the loaded data, as it would keep reading the previous buffer, and `ReloadError::Shared` if other loaded
+/// files use the same loaded data, as they would be left pointing at nothing.
+///
+/// The buffer comes from the resource service's own heap, which ARCropolis has no way to release into, so it is only dropped.
+pub fn evict_loaded_file(hash: Hash40) -> Result<bool, ReloadError> {
+    let arc = resource::arc();
+    let filesystem_info = resource::filesystem_info_mut();
+
+    let file_info = arc.get_file_info_from_hash(hash).map_err(|_| ReloadError::Missing)?;
+    let filepath_index = usize::from(file_info.file_path_index);
+
+    unsafe {
+        nn::os::LockMutex(filesystem_info.mutex);
+    }
+
+    let loaded_filepath = filesystem_info.get_loaded_filepaths()[filepath_index];
+
+    let result = if loaded_filepath.is_loaded == 0 {
+        Ok(false)
+    } else {
+        let ref_count = filesystem_info.get_loaded_datas()[loaded_filepath].ref_count.load(std::sync::atomic::Ordering::SeqCst);
+
+        if ref_count != 0 {
+            Err(ReloadError::InUse(ref_count))
+        } else {
+            let shared = filesystem_info
+                .get_loaded_filepaths()
+                .iter()
+                .enumerate()
+                .filter(|(idx, other)| {
+                    *idx != filepath_index && other.is_loaded != 0 && other.loaded_data_index == loaded_filepath.loaded_data_index
+                })
+                .count();
+
+            if shared != 0 {
+                Err(ReloadError::Shared(shared))
+            } else {
+                let loaded_data = &mut filesystem_info.get_loaded_datas_mut()[loaded_filepath];
+                loaded_data.data = std::ptr::null();
+                loaded_data.state = LoadState::Unloaded;
+                filesystem_info.get_loaded_filepaths_mut()[filepath_index].is_loaded = 0;
+                Ok(true)
+            }
+        }
+    };
+
+    unsafe {
+        nn::os::UnlockMutex(filesystem_info.mutex);
+    }
+
+    result
+}
+
+/// Marks a modded file as stale: refreshes its cached size (patching the LoadedArc again if it grew) and evicts the game's copy
+/// so that the next load re-inflates it through `handle_file_replace`.
+pub fn invalidate(hash: Hash40) -> Result<(), ReloadError> {
+    if !resource::initialized() {
+        return Err(ReloadError::Uninitialized);
+    }
+
+    {
+        let mut fs = GLOBAL_FILESYSTEM.write();
+        if fs.local_hash(hash).is_none() {
+            return Err(ReloadError::NotModded);
+        }
+        fs.refresh_file(hash);
+    }
+
+    match evict_loaded_file(hash) {
+        Ok(true) => info!("Evicted '{}' ({:#x}), it will be reloaded on the next request.", hashes::find(hash), hash.0),
+        Ok(false) => info!("'{}' ({:#x}) is not loaded, it will be reloaded on the next request.", hashes::find(hash), hash.0),
+        Err(e) => return Err(e),
+    }
+
+    Ok(())
+}
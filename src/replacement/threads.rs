@@ -4,9 +4,9 @@ use smash_arc::{ArcLookup, Hash40};
 
 use super::FileInfoFlagsExt;
 use crate::{
-    config, hashes, offsets, reg_w, reg_x,
+    api, config, hashes, offsets, reg_w, reg_x,
     resource::{self, InflateFile, LoadInfo, LoadType},
-    GLOBAL_FILESYSTEM,
+    tracer, GLOBAL_FILESYSTEM,
 };
 
 #[hook(offset = offsets::inflate(), inline)]
@@ -30,9 +30,41 @@ fn inflate_incoming(ctx: &InlineCtx) {
         hashes::find(path_hash).bright_yellow()
     );
 
+    // Plugins get the final say on where a file is loaded from, so ask them before we lock the filesystem
+    let redirect = api::redirect::resolve(path_hash);
+
     let mut fs = GLOBAL_FILESYSTEM.write();
 
-    let should_add = if let Some(path) = fs.hash(path_hash) {
+    let actual_path = match &redirect {
+        Some(api::Redirect::Path(path)) => Some(path.clone()),
+        Some(api::Redirect::Hash(hash)) => fs.hash(*hash),
+        None => fs.hash(path_hash),
+    };
+
+    if tracer::enabled() {
+        tracer::begin(
+            reg_w!(ctx, 21),
+            reg_w!(ctx, 27),
+            file_info.file_path_index.0,
+            path_hash,
+            fs.local_hash(path_hash).map(|x| x.as_path()),
+            actual_path.as_deref(),
+        );
+    }
+
+    let should_add = if let Some(redirect) = redirect {
+        match &redirect {
+            api::Redirect::Hash(hash) => info!(
+                "Redirected file '{}' to '{}' ({:#x}).",
+                hashes::find(path_hash).yellow(),
+                hashes::find(*hash).yellow(),
+                hash.0
+            ),
+            api::Redirect::Path(path) => info!("Redirected file '{}' to '{}'.", hashes::find(path_hash).yellow(), path.display().yellow()),
+        }
+        fs.set_incoming_redirect(path_hash, redirect);
+        true
+    } else if let Some(path) = actual_path {
         info!("Added file '{}' to the queue.", path.display().yellow());
         true
     } else {
@@ -58,6 +90,7 @@ fn inflate_dir_file(arg: u64, out_decomp_data: &mut InflateFile, comp_data: &Inf
 
     if result == 0x0 {
         // returns 0x0 on the very last read, since they can be read in chunks
+        tracer::mark_inflated();
         let hash = crate::GLOBAL_FILESYSTEM.write().get_incoming();
         if let Some(hash) = hash {
             handle_file_replace(hash);
@@ -114,7 +147,12 @@ pub fn handle_file_replace(hash: Hash40) {
         )
     };
 
-    if let Some(size) = fs.load_into(hash, buffer) {
+    let loaded = match fs.take_redirect(hash) {
+        Some(redirect) => fs.load_redirect_into(&redirect, buffer),
+        None => fs.load_into(hash, buffer),
+    };
+
+    if let Some(size) = loaded {
         if arc.get_file_paths()[filepath_index].ext.hash40() == Hash40::from("nutexb") {
             if size < decompressed_size as usize {
                 let (contents, footer) = buffer.split_at_mut((decompressed_size - 0xb0) as usize);
@@ -137,6 +175,7 @@ pub fn handle_file_replace(hash: Hash40) {
             size,
             resource::res_service().buffer_size
         );
+        tracer::record_replace(hash, Some(size));
     } else {
         warn!(
             "Failed to load file '{}' ({:#x}) into buffer with size {:#X}",
@@ -144,6 +183,7 @@ pub fn handle_file_replace(hash: Hash40) {
             hash.0,
             decompressed_size
         );
+        tracer::record_replace(hash, None);
     }
 }
 
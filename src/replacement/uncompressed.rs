@@ -19,6 +19,7 @@ fn memcpy_uncompressed_fix(ctx: &InlineCtx) {
     let buffer_size = reg_x!(ctx, 2) as usize;
     let hash = crate::GLOBAL_FILESYSTEM.write().sub_remaining_bytes(buffer_size);
     if let Some(hash) = hash {
+        crate::tracer::mark_inflated();
         super::threads::handle_file_replace(hash);
     } else {
         let dest = reg_x!(ctx, 0) as *mut c_void;
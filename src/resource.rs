@@ -47,6 +47,9 @@ pub fn res_service_mut() -> &'static mut ResServiceNX {
     unsafe { *addr }
 }
 
+/// Whether the game has loaded the data.arc yet. The address of the global is always valid, it is what it points to that
+/// stays null until then.
 pub fn initialized() -> bool {
-    !offset_to_addr::<&'static FilesystemInfo>(offsets::filesystem_info()).is_null()
+    offsets::is_available(offsets::Feature::FileReplacement)
+        && unsafe { !(*offset_to_addr::<*const FilesystemInfo>(offsets::filesystem_info())).is_null() }
 }
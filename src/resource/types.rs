@@ -155,6 +155,14 @@ impl FilesystemInfo {
     pub fn get_loaded_directories(&self) -> &[LoadedDirectory] {
         unsafe { std::slice::from_raw_parts(self.loaded_directories, self.loaded_directory_len as usize) }
     }
+
+    pub fn get_loaded_filepaths_mut(&mut self) -> &mut [LoadedFilepath] {
+        unsafe { std::slice::from_raw_parts_mut(self.loaded_filepaths, self.loaded_filepath_len as usize) }
+    }
+
+    pub fn get_loaded_datas_mut(&mut self) -> &mut [LoadedData] {
+        unsafe { std::slice::from_raw_parts_mut(self.loaded_datas, self.loaded_data_len as usize) }
+    }
 }
 
 #[repr(C)]
@@ -0,0 +1,336 @@
+use std::{
+    collections::VecDeque,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use smash_arc::Hash40;
+
+use crate::{config, hashes, logging::format_time_string};
+
+const TRACE_PATH: &str = "sd:/ultimate/arcropolis/traces";
+
+/// How many completed loads are kept before the oldest ones start getting dropped
+const MAX_RECORDS: usize = 0x4000;
+
+/// How long the resource service has to stay quiet before the buffer gets written to the SD card
+const IDLE_FLUSH_DELAY: Duration = Duration::from_millis(2000);
+
+/// How many trace files are kept on the SD card, the oldest ones get deleted past that
+const MAX_TRACE_FILES: usize = 16;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TRACER: Lazy<Mutex<Tracer>> = Lazy::new(|| Mutex::new(Tracer::new()));
+
+/// A single resource request, from the moment `inflate_incoming` sees it until the replacement (if any) has been written
+struct LoadRecord {
+    /// What `inflate_incoming` logs as the type of the request (w21). It is used as the track, so requests of the same type
+    /// line up together.
+    load_type: u32,
+    /// Position of the file in the list the resource service is processing (x27)
+    list_index: u32,
+    filepath_index: u32,
+    hash: Hash40,
+    path: Option<PathBuf>,
+    mod_root: Option<PathBuf>,
+    size: Option<usize>,
+    start: u64,
+    /// Set by the inflate hooks once the game is done with the file. Uncompressed files that aren't modded never go through
+    /// them, and are left without one rather than timed until the next request.
+    inflated: Option<u64>,
+    replaced: Option<u64>,
+}
+
+impl LoadRecord {
+    /// Converts the record into Chrome `trace_event` events. Files with a known inflate time get a complete event for it and
+    /// another one for the replacement, the others are only marked with an instant event.
+    fn to_events(&self, events: &mut Vec<TraceEvent>) {
+        let name = hashes::try_find(self.hash).map_or_else(|| format!("{:#x}", self.hash.0), String::from);
+
+        let args = TraceArgs {
+            load_type: self.load_type,
+            list_index: self.list_index,
+            filepath_index: self.filepath_index,
+            hash: format!("{:#x}", self.hash.0),
+            path: self.path.as_ref().map(|path| path.display().to_string()),
+            mod_root: self.mod_root.as_ref().map(|path| path.display().to_string()),
+            size: self.size,
+        };
+
+        let inflated = match self.inflated {
+            Some(inflated) => inflated,
+            None => {
+                events.push(TraceEvent {
+                    name,
+                    cat: "load",
+                    ph: "i",
+                    ts: self.start,
+                    dur: None,
+                    pid: 0,
+                    tid: self.load_type,
+                    args,
+                });
+                return;
+            },
+        };
+
+        events.push(TraceEvent {
+            name: name.clone(),
+            cat: "inflate",
+            ph: "X",
+            ts: self.start,
+            dur: Some(inflated.saturating_sub(self.start)),
+            pid: 0,
+            tid: self.load_type,
+            args: args.clone(),
+        });
+
+        if let Some(replaced) = self.replaced {
+            events.push(TraceEvent {
+                name,
+                cat: "replace",
+                ph: "X",
+                ts: inflated,
+                dur: Some(replaced.saturating_sub(inflated)),
+                pid: 0,
+                tid: self.load_type,
+                args,
+            });
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct TraceArgs {
+    load_type: u32,
+    list_index: u32,
+    filepath_index: u32,
+    hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mod_root: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    ts: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<u64>,
+    pid: u32,
+    tid: u32,
+    args: TraceArgs,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceFile {
+    trace_events: Vec<TraceEvent>,
+}
+
+struct Tracer {
+    pending: Option<LoadRecord>,
+    records: VecDeque<LoadRecord>,
+    last_activity: u64,
+}
+
+impl Tracer {
+    fn new() -> Self {
+        Self {
+            pending: None,
+            records: VecDeque::with_capacity(MAX_RECORDS),
+            last_activity: 0,
+        }
+    }
+
+    /// Moves the pending record into the ring buffer, dropping the oldest record if it is full
+    fn complete_pending(&mut self) {
+        if let Some(record) = self.pending.take() {
+            if self.records.len() == MAX_RECORDS {
+                self.records.pop_front();
+            }
+            self.records.push_back(record);
+        }
+    }
+}
+
+/// Microseconds since the epoch, which is the unit the Chrome trace viewer expects
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records the start of a resource request. The actual path is the file the request will be replaced with, if it is modded.
+pub fn begin(load_type: u32, list_index: u32, filepath_index: u32, hash: Hash40, local: Option<&Path>, actual: Option<&Path>) {
+    if !enabled() {
+        return;
+    }
+
+    // The mod root is whatever is left of the actual path once the local path has been removed
+    let mod_root = match (local.and_then(|x| x.to_str()), actual.and_then(|x| x.to_str())) {
+        (Some(local), Some(actual)) => actual.strip_suffix(local).map(PathBuf::from),
+        _ => None,
+    };
+
+    let time = now();
+    let mut tracer = TRACER.lock();
+
+    // Files that aren't modded never reach handle_file_replace, so they are completed once the next request comes in
+    tracer.complete_pending();
+
+    tracer.pending = Some(LoadRecord {
+        load_type,
+        list_index,
+        filepath_index,
+        hash,
+        path: actual.map(Path::to_path_buf),
+        mod_root,
+        size: None,
+        start: time,
+        inflated: None,
+        replaced: None,
+    });
+    tracer.last_activity = time;
+}
+
+/// Marks the end of the game's own decompression for the pending request
+pub fn mark_inflated() {
+    if !enabled() {
+        return;
+    }
+
+    let time = now();
+    let mut tracer = TRACER.lock();
+
+    if let Some(pending) = tracer.pending.as_mut() {
+        pending.inflated.get_or_insert(time);
+    }
+    tracer.last_activity = time;
+}
+
+/// Marks the end of the replacement for the pending request and completes it
+pub fn record_replace(hash: Hash40, size: Option<usize>) {
+    if !enabled() {
+        return;
+    }
+
+    let time = now();
+    let mut tracer = TRACER.lock();
+
+    if let Some(pending) = tracer.pending.as_mut() {
+        if pending.hash == hash {
+            pending.size = size;
+            pending.replaced = Some(time);
+            tracer.complete_pending();
+        }
+    }
+    tracer.last_activity = time;
+}
+
+/// Takes every buffered record if no file has been requested for a while
+fn take_if_idle() -> Option<VecDeque<LoadRecord>> {
+    let mut tracer = TRACER.lock();
+
+    if tracer.last_activity == 0 || now().saturating_sub(tracer.last_activity) < IDLE_FLUSH_DELAY.as_micros() as u64 {
+        return None;
+    }
+
+    tracer.complete_pending();
+    tracer.last_activity = 0;
+
+    Some(std::mem::take(&mut tracer.records))
+}
+
+/// Writes the records to a new trace file
+fn flush(records: VecDeque<LoadRecord>) {
+    if records.is_empty() {
+        return;
+    }
+
+    let mut trace = TraceFile {
+        trace_events: Vec::with_capacity(records.len() * 2),
+    };
+
+    for record in records.iter() {
+        record.to_events(&mut trace.trace_events);
+    }
+
+    let seconds = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0);
+    let path = Path::new(TRACE_PATH).join(format!("{}.json", format_time_string(seconds)));
+
+    let _ = std::fs::create_dir_all(TRACE_PATH);
+
+    match std::fs::File::create(&path) {
+        Ok(file) => match serde_json::to_writer(BufWriter::new(file), &trace) {
+            Ok(_) => info!("Wrote {} file load(s) to '{}'.", records.len(), path.display()),
+            Err(e) => error!("Failed to write file load trace to '{}'. Reason: {:?}", path.display(), e),
+        },
+        Err(e) => error!("Failed to create file load trace at '{}'. Reason: {:?}", path.display(), e),
+    }
+
+    remove_old_traces();
+}
+
+/// Deletes the oldest trace files past `MAX_TRACE_FILES`, since a new one is written every time the game stops loading
+fn remove_old_traces() {
+    let entries = match std::fs::read_dir(TRACE_PATH) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut traces: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+
+    // Timestamps sort chronologically, so the oldest traces come first
+    traces.sort();
+
+    let excess = traces.len().saturating_sub(MAX_TRACE_FILES);
+
+    for path in traces.iter().take(excess) {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Failed to delete old file load trace '{}'. Reason: {:?}", path.display(), e);
+        }
+    }
+}
+
+/// Enables the tracer if requested in the configuration, and starts the thread responsible for writing traces
+/// whenever the game stops loading files for a while.
+pub fn init() {
+    if !config::trace_file_loads() {
+        return;
+    }
+
+    ENABLED.store(true, Ordering::SeqCst);
+
+    let result = std::thread::Builder::new().stack_size(0x40000).spawn(|| loop {
+        std::thread::sleep(Duration::from_millis(500));
+
+        if let Some(records) = take_if_idle() {
+            flush(records);
+        }
+    });
+
+    if let Err(e) = result {
+        ENABLED.store(false, Ordering::SeqCst);
+        error!("Failed to start the file load tracer. Reason: {:?}", e);
+    }
+}
@@ -1,13 +1,34 @@
-use std::fmt;
+use std::{
+    fmt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
-use gh_updater::ReleaseFinderConfig;
+use arcropolis_updater::{Installer, Recovery};
 use semver::Version;
-use zip::ZipArchive;
+
+use crate::{
+    config,
+    menus::{MainEntry, UpdateChoice},
+};
+
+mod github;
+pub mod mods;
+
+pub use arcropolis_updater::{LocalSource, ReleaseSource};
+pub use github::GithubSource;
+
+/// Folder holding a release to install instead of looking on GitHub, see `LocalSource`
+const LOCAL_RELEASE_PATH: &str = "sd:/ultimate/arcropolis/local_release";
+
+/// How long a deferred update is kept quiet for
+const DEFER_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 pub enum VersionDifference {
     ChangeToStable(String),
     ChangeToBeta(String),
     Regular(String),
+    Pinned(String),
 }
 
 impl fmt::Display for VersionDifference {
@@ -16,6 +37,7 @@ impl fmt::Display for VersionDifference {
             Self::ChangeToStable(ver) => write!(f, "An uninstalled stable version of ARCropolis ({})", ver),
             Self::ChangeToBeta(ver) => write!(f, "A new beta version of ARCropolis ({})", ver),
             Self::Regular(ver) => write!(f, "A new update for ARCropolis ({})", ver),
+            Self::Pinned(ver) => write!(f, "The version of ARCropolis the updater is pinned to ({})", ver),
         }
     }
 }
@@ -35,72 +57,164 @@ fn compare_tags(current: &str, target: &str) -> Result<Option<VersionDifference>
     }
 }
 
-pub fn check_for_updates<F>(beta_enabled: bool, f: F)
+/// A release in the local release folder takes priority over GitHub
+pub fn default_source() -> Box<dyn ReleaseSource> {
+    if Path::new(LOCAL_RELEASE_PATH).exists() {
+        info!("Looking for updates in '{}' instead of GitHub.", LOCAL_RELEASE_PATH);
+        Box::new(LocalSource {
+            path: LOCAL_RELEASE_PATH.into(),
+            asset: String::from("release.zip"),
+        })
+    } else {
+        Box::new(GithubSource::arcropolis())
+    }
+}
+
+/// Finishes or undoes an interrupted update. Must be called on boot, before anything else gets a chance to crash.
+pub fn recover() {
+    match Installer::new("sd:/").recover() {
+        Recovery::Nothing => {},
+        Recovery::Booting(version) => info!("Booting ARCropolis {} for the first time since the update.", version),
+        Recovery::RolledBack(version) => {
+            error!("The update did not finish or the game failed to boot after it, ARCropolis was rolled back to {}.", version);
+            // The session isn't tracked yet, and the snapshot of the one that failed has to be kept for its report
+            unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
+        },
+    }
+}
+
+/// Called once the game reaches the title screen, after which the update is kept for good
+pub fn confirm_boot() {
+    if let Some(version) = Installer::new("sd:/").confirm() {
+        info!("ARCropolis {} booted successfully, the previous version is no longer kept around.", version);
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)
+}
+
+/// Whether the user asked to be reminded of this version later, and that time hasn't come yet
+fn is_deferred(version: &str) -> bool {
+    let storage = config::GLOBAL_CONFIG.lock().unwrap();
+    let deferred: Option<String> = storage.get_field("deferred_update").ok();
+    let until: u64 = storage.get_field("deferred_update_until").unwrap_or(0);
+
+    deferred.as_deref() == Some(version) && now() < until
+}
+
+fn remember_choice(version: &str, choice: UpdateChoice) {
+    let mut storage = config::GLOBAL_CONFIG.lock().unwrap();
+
+    let result = match choice {
+        UpdateChoice::Install => return,
+        UpdateChoice::Defer => storage
+            .set_field("deferred_update", version)
+            .and_then(|_| storage.set_field("deferred_update_until", now() + DEFER_DURATION.as_secs())),
+        UpdateChoice::Skip => {
+            let mut settings = config::Settings::load(&*storage);
+            settings.skipped_updates.push(version.to_string());
+            settings.save(&mut *storage)
+        },
+    };
+
+    if let Err(e) = result.and_then(|_| storage.flush()) {
+        error!("Failed to remember the choice for ARCropolis {}. Reason: {:?}", version, e);
+    }
+
+    config::reload_settings(&*storage);
+}
+
+/// Looks for a release to update to and asks the user what to do with it through `prompt`, which is given the release
+/// notes if the release has any. Pinned versions and the versions the user skipped or deferred are honored.
+pub fn check_for_updates<F>(source: &dyn ReleaseSource, beta_enabled: bool, prompt: F)
 where
-    F: Fn(VersionDifference) -> bool,
+    F: Fn(VersionDifference, Option<MainEntry>) -> UpdateChoice,
 {
-    let release = ReleaseFinderConfig::new("ARCropolis")
-        .with_author("Raytwo")
-        .with_repository("ARCropolis")
-        .with_prereleases(beta_enabled)
-        .find_release();
-
-    let (release, prerelease) = match release {
-        Ok(r) => r,
-        Err(e) => {
-            error!("Failed to check for updates: {:?}", e);
-            return;
-        },
+    let (update_pin, skipped_updates) = {
+        let settings = config::settings();
+        (settings.update_pin.clone(), settings.skipped_updates.clone())
     };
 
-    let prerelease_tag = prerelease
-        .as_ref()
-        .map(|x| Version::parse(x.get_release_tag().trim_start_matches('v')).expect("Failed to parse version strings!"));
-    let release_tag = release
-        .as_ref()
-        .map(|x| Version::parse(x.get_release_tag().trim_start_matches('v')).expect("Failed to parse version strings!"));
+    let update_pin = update_pin.map(|pin| pin.trim_start_matches('v').to_string());
+
+    if update_pin.as_deref() == Some(env!("CARGO_PKG_VERSION")) {
+        info!("The updater is pinned to the current version of ARCropolis, not looking for updates.");
+        return;
+    }
+
+    // Pinning can also mean going back to an older version, so the pinned release is looked up by its tag
+    let release = match update_pin.as_deref() {
+        Some(pin) => source.find_tag(pin),
+        None => source.find_latest(beta_enabled),
+    };
 
-    let release = match (prerelease_tag, release_tag) {
-        (None, None) => {
-            error!("No github releases were found!");
+    let release = match release {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            match update_pin.as_deref() {
+                Some(pin) => error!("The updater is pinned to ARCropolis {}, but no release was found for it!", pin),
+                None => error!("No releases were found!"),
+            }
             return;
         },
-        (prerelease_tag, release_tag) => {
-            if prerelease_tag > release_tag {
-                prerelease.unwrap()
-            } else {
-                // even if they are equal it won't matter
-                release.unwrap()
-            }
+        Err(e) => {
+            error!("Failed to check for updates: {}", e);
+            return;
         },
     };
 
-    let version_difference = match compare_tags(env!("CARGO_PKG_VERSION"), release.get_release_tag().trim_start_matches('v')) {
-        Ok(diff) => diff,
-        Err(e) => {
-            error!("Failed to parse version strings: {:?}", e);
-            return;
+    let version_difference = match update_pin.as_deref() {
+        Some(pin) => Some(VersionDifference::Pinned(pin.to_string())),
+        None => match compare_tags(env!("CARGO_PKG_VERSION"), release.tag()) {
+            Ok(diff) => diff,
+            Err(e) => {
+                error!("Failed to parse version strings: {:?}", e);
+                return;
+            },
         },
     };
 
-    if let Some(update_kind) = version_difference {
-        if !f(update_kind) {
-            return;
-        }
-        if let Some(release) = release.get_asset_by_name("release.zip") {
-            let mut zip = match ZipArchive::new(std::io::Cursor::new(release)) {
-                Ok(zip) => zip,
-                Err(e) => {
-                    error!("Failed to parse zip data: {:?}", e);
-                    return;
-                },
-            };
-
-            if let Err(e) = zip.extract("sd:/") {
-                panic!("ARCropolis failed to extract update ZIP. Reason: {:?}", e);
-            }
+    let update_kind = match version_difference {
+        Some(update_kind) => update_kind,
+        None => return,
+    };
 
-            unsafe { skyline::nn::oe::RequestToRelaunchApplication() };
-        }
+    if skipped_updates.iter().any(|skipped| skipped.trim_start_matches('v') == release.tag()) {
+        info!("ARCropolis {} is available, but was skipped.", release.tag());
+        return;
+    }
+
+    if is_deferred(release.tag()) {
+        info!("ARCropolis {} is available, but was deferred.", release.tag());
+        return;
+    }
+
+    let notes = release.changelog().and_then(|changelog| match toml::from_str(&changelog) {
+        Ok(notes) => Some(notes),
+        Err(e) => {
+            warn!("The release notes of ARCropolis {} could not be parsed. Reason: {:?}", release.tag(), e);
+            None
+        },
+    });
+
+    let choice = prompt(update_kind, notes);
+    remember_choice(release.tag(), choice);
+
+    if choice != UpdateChoice::Install {
+        return;
+    }
+
+    // Releases are made by hand and don't always publish a checksum, in which case the installer warns about it
+    let result = release
+        .download()
+        .and_then(|(zip, checksum)| Installer::new("sd:/").install(env!("CARGO_PKG_VERSION"), release.tag(), &zip, checksum.as_deref()));
+
+    match result {
+        Ok(_) => crate::relaunch(),
+        Err(e) => {
+            error!("Failed to install ARCropolis {}: {}", release.tag(), e);
+            crate::dialog_error(format!("ARCropolis failed to install the update to {}, nothing was changed.<br>{}", release.tag(), e));
+        },
     }
 }
@@ -0,0 +1,106 @@
+use arcropolis_updater::{parse_checksum, Release, ReleaseSource, UpdateError};
+use gh_updater::ReleaseFinderConfig;
+use semver::Version;
+
+fn get(url: &str) -> Result<minreq::Response, minreq::Error> {
+    minreq::get(url).with_header("User-Agent", "ARCropolis").send()
+}
+
+/// Releases of a GitHub repository, the checksum being looked for in an asset named after the release asset with a
+/// `.sha256` extension
+pub struct GithubSource {
+    pub owner: String,
+    pub repository: String,
+    pub asset: String,
+}
+
+impl GithubSource {
+    pub fn arcropolis() -> Self {
+        Self {
+            owner: String::from("Raytwo"),
+            repository: String::from("ARCropolis"),
+            asset: String::from("release.zip"),
+        }
+    }
+}
+
+struct GithubRelease {
+    tag: String,
+    asset_name: String,
+    asset: Box<dyn Fn(&str) -> Option<Vec<u8>>>,
+}
+
+impl Release for GithubRelease {
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn changelog(&self) -> Option<String> {
+        (self.asset)("changelog.toml").and_then(|data| String::from_utf8(data).ok())
+    }
+
+    fn download(&self) -> Result<(Vec<u8>, Option<String>), UpdateError> {
+        let checksum = (self.asset)(&format!("{}.sha256", self.asset_name)).and_then(|data| parse_checksum(&data));
+        let zip = (self.asset)(&self.asset_name).ok_or_else(|| UpdateError::MissingAsset(self.asset_name.clone()))?;
+
+        Ok((zip, checksum))
+    }
+}
+
+impl ReleaseSource for GithubSource {
+    fn find_latest(&self, beta_enabled: bool) -> Result<Option<Box<dyn Release>>, UpdateError> {
+        let (release, prerelease) = ReleaseFinderConfig::new(&self.repository)
+            .with_author(&self.owner)
+            .with_repository(&self.repository)
+            .with_prereleases(beta_enabled)
+            .find_release()
+            .map_err(|e| UpdateError::Source(format!("{:?}", e)))?;
+
+        let parse_tag = |tag: &str| Version::parse(tag.trim_start_matches('v')).map_err(UpdateError::Version);
+
+        let prerelease_tag = prerelease.as_ref().map(|x| parse_tag(x.get_release_tag())).transpose()?;
+        let release_tag = release.as_ref().map(|x| parse_tag(x.get_release_tag())).transpose()?;
+
+        // Even if they are equal it won't matter
+        let release = if prerelease_tag > release_tag { prerelease } else { release };
+
+        Ok(release.map(|release| {
+            Box::new(GithubRelease {
+                tag: release.get_release_tag().trim_start_matches('v').to_string(),
+                asset_name: self.asset.clone(),
+                asset: Box::new(move |name| release.get_asset_by_name(name)),
+            }) as Box<dyn Release>
+        }))
+    }
+
+    fn find_tag(&self, tag: &str) -> Result<Option<Box<dyn Release>>, UpdateError> {
+        let tag = tag.trim_start_matches('v');
+
+        // Releases have been tagged both with and without the prefix
+        for candidate in [format!("v{}", tag), tag.to_string()] {
+            let url = format!("https://api.github.com/repos/{}/{}/releases/tags/{}", self.owner, self.repository, candidate);
+            let response = get(&url).map_err(|e| UpdateError::Source(format!("{:?}", e)))?;
+
+            match response.status_code {
+                200 => {},
+                404 => continue,
+                code => return Err(UpdateError::Source(format!("GitHub answered {} for the release {}", code, candidate))),
+            }
+
+            let download_url = format!("https://github.com/{}/{}/releases/download/{}", self.owner, self.repository, candidate);
+
+            return Ok(Some(Box::new(GithubRelease {
+                tag: tag.to_string(),
+                asset_name: self.asset.clone(),
+                asset: Box::new(move |name| {
+                    get(&format!("{}/{}", download_url, name))
+                        .ok()
+                        .filter(|response| response.status_code == 200)
+                        .map(|response| response.into_bytes())
+                }),
+            })));
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use arcropolis_updater::{
+    mods::{install, is_newer},
+    LocalSource,
+    ReleaseSource,
+};
+use serde::Deserialize;
+
+use super::GithubSource;
+use crate::config;
+
+/// Where mod updates are extracted to in `staging` before being moved into the mod folder, and where the previous
+/// version of an updated mod is kept in `backup`
+const MOD_UPDATES_PATH: &str = "sd:/ultimate/arcropolis/mod_updates";
+
+/// The `[update]` table of a mod's info.toml
+///
+/// ```toml
+/// [update]
+/// owner = "Raytwo"
+/// repository = "SomeMod"
+/// asset = "SomeMod.zip"
+/// ```
+///
+/// A `path` can be given instead of `owner` and `repository` to look for releases in a folder, laid out like the one
+/// `LocalSource` expects.
+#[derive(Deserialize, Debug)]
+struct ModUpdateSource {
+    owner: Option<String>,
+    repository: Option<String>,
+    path: Option<PathBuf>,
+    asset: String,
+}
+
+impl ModUpdateSource {
+    fn source(&self) -> Option<Box<dyn ReleaseSource>> {
+        if let Some(path) = self.path.as_ref() {
+            return Some(Box::new(LocalSource {
+                path: path.clone(),
+                asset: self.asset.clone(),
+            }));
+        }
+
+        Some(Box::new(GithubSource {
+            owner: self.owner.clone()?,
+            repository: self.repository.clone()?,
+            asset: self.asset.clone(),
+        }))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ModInfo {
+    display_name: Option<String>,
+    version: Option<String>,
+    update: Option<ModUpdateSource>,
+}
+
+/// A mod whose info.toml declares where its updates are published
+struct UpdatableMod {
+    name: String,
+    path: PathBuf,
+    version: String,
+    source: Box<dyn ReleaseSource>,
+}
+
+fn updatable_mods() -> Vec<UpdatableMod> {
+    let entries = match std::fs::read_dir(config::umm_path()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to read '{}'. Reason: {:?}", config::umm_path().display(), e);
+            return Vec::new();
+        },
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+
+            if !path.is_dir() {
+                return None;
+            }
+
+            let info = std::fs::read_to_string(path.join("info.toml")).ok()?;
+
+            let info: ModInfo = match toml::from_str(&info) {
+                Ok(info) => info,
+                Err(e) => {
+                    warn!("Failed to parse the info.toml of '{}'. Reason: {:?}", path.display(), e);
+                    return None;
+                },
+            };
+
+            let folder_name = path.file_name()?.to_string_lossy().to_string();
+
+            let update = info.update?;
+            let source = match update.source() {
+                Some(source) => source,
+                None => {
+                    warn!("The update source of '{}' needs either a path, or an owner and a repository.", folder_name);
+                    return None;
+                },
+            };
+
+            let version = match info.version {
+                Some(version) => version,
+                None => {
+                    warn!("'{}' declares an update source but no version, it cannot be checked for updates.", folder_name);
+                    return None;
+                },
+            };
+
+            Some(UpdatableMod {
+                name: info.display_name.unwrap_or(folder_name),
+                path,
+                version,
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Looks for updates to every mod that declares an update source in its info.toml, and offers to install each of them
+pub fn check_for_mod_updates() {
+    let mods = updatable_mods();
+
+    if mods.is_empty() {
+        skyline_web::DialogOk::ok("None of your mods declare an update source in their info.toml.");
+        return;
+    }
+
+    let mut failed = Vec::new();
+    let mut available = Vec::new();
+
+    for updatable in mods {
+        match updatable.source.find_latest(false) {
+            Ok(Some(release)) => match is_newer(&updatable.version, release.tag()) {
+                Some(false) => debug!("'{}' is up to date ({}).", updatable.name, updatable.version),
+                newer => available.push((updatable, release, newer.is_some())),
+            },
+            Ok(None) => debug!("'{}' is up to date ({}).", updatable.name, updatable.version),
+            Err(e) => {
+                error!("Failed to check '{}' for updates: {}", updatable.name, e);
+                failed.push(updatable.name);
+            },
+        }
+    }
+
+    if available.is_empty() {
+        if failed.is_empty() {
+            skyline_web::DialogOk::ok("All of your mods are up to date.");
+        } else {
+            crate::dialog_error(format!("The following mods could not be checked for updates:<br>{}", failed.join("<br>")));
+        }
+        return;
+    }
+
+    let mut updated = 0;
+
+    for (updatable, release, is_known_newer) in available.iter() {
+        let question = if *is_known_newer {
+            format!(
+                "An update for {} is available ({} -> {}).<br>Do you want to install it? The current version will be kept as a backup.",
+                updatable.name,
+                updatable.version,
+                release.tag()
+            )
+        } else {
+            format!(
+                "{} has a release ({}) that differs from the installed version ({}), but it can't be told which one is newer.<br>Do you \
+                 want to install it? The current version will be kept as a backup.",
+                updatable.name,
+                release.tag(),
+                updatable.version
+            )
+        };
+
+        if !skyline_web::Dialog::yes_no(question) {
+            continue;
+        }
+
+        let result = release
+            .download()
+            .and_then(|(zip, checksum)| install(&updatable.path, &zip, checksum.as_deref(), Path::new(MOD_UPDATES_PATH)));
+
+        match result {
+            Ok(backup) => {
+                info!(
+                    "Updated '{}' from {} to {}, the previous version was moved to '{}'.",
+                    updatable.name,
+                    updatable.version,
+                    release.tag(),
+                    backup.display()
+                );
+                updated += 1;
+            },
+            Err(e) => {
+                error!("Failed to update '{}' to {}: {}", updatable.name, release.tag(), e);
+                failed.push(updatable.name.clone());
+            },
+        }
+    }
+
+    if !failed.is_empty() {
+        crate::dialog_error(format!("The following mods could not be checked or updated:<br>{}", failed.join("<br>")));
+    }
+
+    // Acquire the filesystem so we can check if it's already finished or not (for boot-time mod manager)
+    if updated > 0
+        && crate::GLOBAL_FILESYSTEM.try_read().is_some()
+        && skyline_web::Dialog::yes_no(format!("{} mod(s) were updated.<br>Would you like to reboot the game to load them?", updated))
+    {
+        crate::relaunch();
+    }
+}
@@ -0,0 +1,464 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+use crate::{verify_checksum, UpdateError};
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+enum UpdateState {
+    /// The files are being moved into place, so the install might be half updated
+    Staged,
+    /// Every file is in place, the new version hasn't been booted yet
+    Committed,
+    /// The new version is booting. Seeing this on boot means the previous attempt never made it to the title screen.
+    Booting,
+}
+
+/// Keeps track of an update until the new version boots successfully, so that it can be rolled back
+#[derive(Serialize, Deserialize)]
+struct UpdateManifest {
+    from: String,
+    to: String,
+    /// Every file written by the update, relative to the root
+    files: Vec<PathBuf>,
+    /// The files that existed before the update, and that have a copy in the backup folder
+    backed_up: Vec<PathBuf>,
+    state: UpdateState,
+}
+
+/// What `Installer::recover` did on boot
+#[derive(Debug, PartialEq, Eq)]
+pub enum Recovery {
+    Nothing,
+    /// This is the first boot of a new version
+    Booting(String),
+    /// The update was undone, and the game has to be restarted to use the previous version again
+    RolledBack(String),
+}
+
+/// Installs updates under a root folder, which is `sd:/` on the console
+pub struct Installer {
+    root: PathBuf,
+}
+
+/// Replaces a file, since renaming over an existing file is not allowed on the SD card
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if to.exists() {
+        std::fs::remove_file(to)?;
+    }
+
+    std::fs::rename(from, to)
+}
+
+impl Installer {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn update_path(&self) -> PathBuf {
+        self.root.join("ultimate/arcropolis/update")
+    }
+
+    fn staging_path(&self) -> PathBuf {
+        self.update_path().join("staging")
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.update_path().join("backup")
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.update_path().join("manifest.json")
+    }
+
+    fn read_manifest(&self) -> Option<UpdateManifest> {
+        let data = std::fs::read(self.manifest_path()).ok()?;
+
+        match serde_json::from_slice(&data) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                error!("Failed to parse the update manifest. Reason: {:?}", e);
+                None
+            },
+        }
+    }
+
+    fn write_manifest(&self, manifest: &UpdateManifest) -> Result<(), UpdateError> {
+        let json = serde_json::to_vec_pretty(manifest).map_err(|e| UpdateError::Manifest(e.to_string()))?;
+
+        // Write it next to the previous one first, so that a failed write never leaves a broken manifest behind
+        let temp_path = self.manifest_path().with_extension("json.tmp");
+        std::fs::write(&temp_path, json)?;
+        replace_file(&temp_path, &self.manifest_path())?;
+
+        Ok(())
+    }
+
+    fn clear(&self) {
+        if self.update_path().exists() {
+            if let Err(e) = std::fs::remove_dir_all(self.update_path()) {
+                warn!("Failed to clean up '{}'. Reason: {:?}", self.update_path().display(), e);
+            }
+        }
+    }
+
+    /// Checks the release against its checksum if it has one, extracts it to a staging folder, backs up every file it would overwrite
+    /// and only then moves the new files into place. Anything going wrong along the way restores the previous files.
+    pub fn install(&self, from: &str, to: &str, zip: &[u8], expected_sha256: Option<&str>) -> Result<(), UpdateError> {
+        verify_checksum(&format!("ARCropolis {}", to), zip, expected_sha256)?;
+
+        if let Some(manifest) = self.read_manifest() {
+            warn!("An update to {} was still pending, rolling it back before installing {}.", manifest.to, to);
+            self.rollback(&manifest);
+        }
+
+        self.clear();
+
+        let mut archive = ZipArchive::new(Cursor::new(zip))?;
+
+        let files: Vec<PathBuf> = (0..archive.len())
+            .filter_map(|index| {
+                let file = archive.by_index(index).ok()?;
+                if file.is_dir() {
+                    None
+                } else {
+                    file.enclosed_name().map(|path| path.to_path_buf())
+                }
+            })
+            .collect();
+
+        if let Err(e) = archive.extract(self.staging_path()) {
+            self.clear();
+            return Err(e.into());
+        }
+
+        let mut backed_up = Vec::new();
+
+        for file in files.iter() {
+            let target = self.root.join(file);
+
+            if !target.exists() {
+                continue;
+            }
+
+            let backup = self.backup_path().join(file);
+            let result = backup
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::copy(&target, &backup).map(|_| ()));
+
+            if let Err(e) = result {
+                self.clear();
+                return Err(e.into());
+            }
+
+            backed_up.push(file.clone());
+        }
+
+        let mut manifest = UpdateManifest {
+            from: from.to_string(),
+            to: to.to_string(),
+            files,
+            backed_up,
+            state: UpdateState::Staged,
+        };
+
+        if let Err(e) = self.write_manifest(&manifest) {
+            self.clear();
+            return Err(e);
+        }
+
+        for file in manifest.files.iter() {
+            if let Err(e) = replace_file(&self.staging_path().join(file), &self.root.join(file)) {
+                error!("Failed to move '{}' into place, rolling the update back. Reason: {:?}", file.display(), e);
+                self.rollback(&manifest);
+                return Err(e.into());
+            }
+        }
+
+        manifest.state = UpdateState::Committed;
+
+        if let Err(e) = self.write_manifest(&manifest) {
+            self.rollback(&manifest);
+            return Err(e);
+        }
+
+        let _ = std::fs::remove_dir_all(self.staging_path());
+
+        Ok(())
+    }
+
+    /// Puts back every file that was backed up and removes the ones the update added
+    fn rollback(&self, manifest: &UpdateManifest) {
+        for file in manifest.files.iter() {
+            let target = self.root.join(file);
+
+            let result = if manifest.backed_up.contains(file) {
+                replace_file(&self.backup_path().join(file), &target)
+            } else if target.exists() {
+                std::fs::remove_file(&target)
+            } else {
+                Ok(())
+            };
+
+            if let Err(e) = result {
+                error!("Failed to restore '{}' while rolling back the update. Reason: {:?}", target.display(), e);
+            }
+        }
+
+        self.clear();
+    }
+
+    /// Called early on boot to finish or undo an update that was interrupted
+    pub fn recover(&self) -> Recovery {
+        let mut manifest = match self.read_manifest() {
+            Some(manifest) => manifest,
+            None => {
+                // Leftovers of an update that failed before the manifest was written
+                self.clear();
+                return Recovery::Nothing;
+            },
+        };
+
+        match manifest.state {
+            UpdateState::Staged | UpdateState::Booting => {
+                self.rollback(&manifest);
+                Recovery::RolledBack(manifest.from)
+            },
+            UpdateState::Committed => {
+                manifest.state = UpdateState::Booting;
+
+                if let Err(e) = self.write_manifest(&manifest) {
+                    error!("Failed to update the update manifest. Reason: {:?}", e);
+                }
+
+                Recovery::Booting(manifest.to)
+            },
+        }
+    }
+
+    /// Called once the new version booted successfully, after which the update can no longer be rolled back
+    pub fn confirm(&self) -> Option<String> {
+        let manifest = self.read_manifest().filter(|manifest| manifest.state == UpdateState::Booting)?;
+        self.clear();
+        Some(manifest.to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sha256_hex,
+        testing::{make_zip, TempRoot},
+        LocalSource,
+        Release,
+        ReleaseSource,
+    };
+
+    /// Always offers the same release, with whatever checksum it is given
+    struct FakeSource {
+        zip: Vec<u8>,
+        sha256: String,
+    }
+
+    struct FakeRelease {
+        zip: Vec<u8>,
+        sha256: String,
+    }
+
+    impl Release for FakeRelease {
+        fn tag(&self) -> &str {
+            "2.0.0"
+        }
+
+        fn changelog(&self) -> Option<String> {
+            None
+        }
+
+        fn download(&self) -> Result<(Vec<u8>, Option<String>), UpdateError> {
+            Ok((self.zip.clone(), Some(self.sha256.clone())))
+        }
+    }
+
+    impl ReleaseSource for FakeSource {
+        fn find_latest(&self, _beta_enabled: bool) -> Result<Option<Box<dyn Release>>, UpdateError> {
+            Ok(Some(Box::new(FakeRelease {
+                zip: self.zip.clone(),
+                sha256: self.sha256.clone(),
+            })))
+        }
+
+        fn find_tag(&self, tag: &str) -> Result<Option<Box<dyn Release>>, UpdateError> {
+            Ok(self.find_latest(false)?.filter(|release| release.tag() == tag.trim_start_matches('v')))
+        }
+    }
+
+    impl FakeSource {
+        fn new(files: &[(&str, &str)]) -> Self {
+            let zip = make_zip(files);
+            let sha256 = sha256_hex(&zip);
+            Self { zip, sha256 }
+        }
+    }
+
+    /// Goes through the same steps as `check_for_updates` once the user agreed to install the release
+    fn install_from(source: &dyn ReleaseSource, installer: &Installer) -> Result<(), UpdateError> {
+        let release = source.find_latest(false)?.unwrap();
+        let (zip, checksum) = release.download()?;
+        installer.install("1.0.0", release.tag(), &zip, checksum.as_deref())
+    }
+
+    fn previous_install(name: &str) -> TempRoot {
+        let root = TempRoot::new(name);
+        root.write("atmosphere/plugin.nro", "old");
+        root.write("ultimate/arcropolis/config.toml", "kept");
+        root
+    }
+
+    fn release() -> FakeSource {
+        FakeSource::new(&[("atmosphere/plugin.nro", "new"), ("atmosphere/added.nro", "added")])
+    }
+
+    #[test]
+    fn install_and_confirm() {
+        let root = previous_install("confirm");
+        let installer = Installer::new(&root.0);
+
+        install_from(&release(), &installer).unwrap();
+
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("new"));
+        assert_eq!(root.read("atmosphere/added.nro").as_deref(), Some("added"));
+        assert_eq!(root.read("ultimate/arcropolis/config.toml").as_deref(), Some("kept"));
+
+        assert_eq!(installer.recover(), Recovery::Booting("2.0.0".to_string()));
+        assert_eq!(installer.confirm(), Some("2.0.0".to_string()));
+        assert!(!installer.update_path().exists());
+
+        // Nothing is left to confirm or roll back
+        assert_eq!(installer.confirm(), None);
+        assert_eq!(installer.recover(), Recovery::Nothing);
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn checksum_mismatch_leaves_the_tree_unchanged() {
+        let root = previous_install("checksum");
+        let installer = Installer::new(&root.0);
+
+        let mut source = release();
+        source.sha256 = sha256_hex(b"something else");
+
+        assert!(matches!(install_from(&source, &installer), Err(UpdateError::Checksum { .. })));
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("old"));
+        assert_eq!(root.read("atmosphere/added.nro"), None);
+        assert!(!installer.update_path().exists());
+    }
+
+    #[test]
+    fn failed_move_rolls_back() {
+        let root = previous_install("rollback");
+        // A file standing where the release expects a folder, so the last file can't be moved into place
+        root.write("atmosphere/blocker", "not a folder");
+        let installer = Installer::new(&root.0);
+
+        let source = FakeSource::new(&[
+            ("atmosphere/plugin.nro", "new"),
+            ("atmosphere/added.nro", "added"),
+            ("atmosphere/blocker/file.txt", "unreachable"),
+        ]);
+
+        assert!(matches!(install_from(&source, &installer), Err(UpdateError::Io(_))));
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("old"));
+        assert_eq!(root.read("atmosphere/added.nro"), None);
+        assert_eq!(root.read("atmosphere/blocker").as_deref(), Some("not a folder"));
+        assert!(!installer.update_path().exists());
+        assert_eq!(installer.recover(), Recovery::Nothing);
+    }
+
+    #[test]
+    fn recover_rolls_back_a_staged_update() {
+        let root = previous_install("staged");
+        let installer = Installer::new(&root.0);
+
+        install_from(&release(), &installer).unwrap();
+
+        // Pretend the console was turned off while the files were being moved into place
+        let mut manifest = installer.read_manifest().unwrap();
+        manifest.state = UpdateState::Staged;
+        installer.write_manifest(&manifest).unwrap();
+
+        assert_eq!(installer.recover(), Recovery::RolledBack("1.0.0".to_string()));
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("old"));
+        assert_eq!(root.read("atmosphere/added.nro"), None);
+        assert!(!installer.update_path().exists());
+    }
+
+    #[test]
+    fn recover_rolls_back_an_update_that_failed_to_boot() {
+        let root = previous_install("booting");
+        let installer = Installer::new(&root.0);
+
+        install_from(&release(), &installer).unwrap();
+        assert_eq!(installer.recover(), Recovery::Booting("2.0.0".to_string()));
+
+        // The game never reached the title screen, so the next boot still finds the update booting
+        assert_eq!(installer.recover(), Recovery::RolledBack("1.0.0".to_string()));
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("old"));
+        assert_eq!(root.read("atmosphere/added.nro"), None);
+        assert_eq!(installer.confirm(), None);
+    }
+
+    #[test]
+    fn install_from_a_local_release() {
+        let root = previous_install("local");
+        let installer = Installer::new(&root.0);
+
+        let fake = release();
+        root.write("local_release/version.txt", "v2.0.0\n");
+        root.write("local_release/release.zip.sha256", &format!("{}  release.zip\n", fake.sha256));
+        std::fs::write(root.0.join("local_release/release.zip"), &fake.zip).unwrap();
+
+        let source = LocalSource {
+            path: root.0.join("local_release"),
+            asset: String::from("release.zip"),
+        };
+
+        install_from(&source, &installer).unwrap();
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("new"));
+        assert_eq!(installer.recover(), Recovery::Booting("2.0.0".to_string()));
+    }
+
+    #[test]
+    fn find_a_local_release_by_tag() {
+        let root = TempRoot::new("local-tag");
+        root.write("local_release/version.txt", "2.0.0");
+
+        let source = LocalSource {
+            path: root.0.join("local_release"),
+            asset: String::from("release.zip"),
+        };
+
+        assert_eq!(source.find_tag("v2.0.0").unwrap().map(|release| release.tag().to_string()).as_deref(), Some("2.0.0"));
+        assert!(source.find_tag("1.0.0").unwrap().is_none());
+    }
+
+    #[test]
+    fn install_without_a_checksum() {
+        let root = previous_install("no-checksum");
+        let installer = Installer::new(&root.0);
+
+        installer.install("1.0.0", "2.0.0", &release().zip, None).unwrap();
+        assert_eq!(root.read("atmosphere/plugin.nro").as_deref(), Some("new"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Installs updates of ARCropolis and of mods, and the sources they are found in.
+//! Nothing in here talks to the console, so that it can be tested on the host.
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[macro_use]
+extern crate log;
+
+mod install;
+pub mod mods;
+mod source;
+#[cfg(test)]
+mod testing;
+
+pub use install::{Installer, Recovery};
+pub use source::{parse_checksum, LocalSource, Release, ReleaseSource};
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("failed to look for releases: {0}")]
+    Source(String),
+    #[error("failed to parse the version: {0}")]
+    Version(#[from] semver::Error),
+    #[error("the release is missing the '{0}' asset")]
+    MissingAsset(String),
+    #[error("the release does not match its checksum (expected {expected}, got {actual})")]
+    Checksum { expected: String, actual: String },
+    #[error("failed to read the release: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("failed to write the update manifest: {0}")]
+    Manifest(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Fails if the data doesn't match the checksum. A missing checksum is only warned about, as not every release has one.
+pub(crate) fn verify_checksum(name: &str, data: &[u8], expected_sha256: Option<&str>) -> Result<(), UpdateError> {
+    let expected_sha256 = match expected_sha256 {
+        Some(expected_sha256) => expected_sha256,
+        None => {
+            warn!("The release of {} does not come with a checksum, it is installed without being verified.", name);
+            return Ok(());
+        },
+    };
+
+    let actual_sha256 = sha256_hex(data);
+
+    if actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        Ok(())
+    } else {
+        Err(UpdateError::Checksum {
+            expected: expected_sha256.to_string(),
+            actual: actual_sha256,
+        })
+    }
+}
@@ -0,0 +1,236 @@
+//! Updating a mod folder in place, keeping the version it replaces as a backup
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use semver::Version;
+use zip::ZipArchive;
+
+use crate::{verify_checksum, UpdateError};
+
+/// Mod authors don't always use semver, in which case there is no telling if a different version is newer. `None` is
+/// returned for those, and the user has to decide.
+pub fn is_newer(current: &str, target: &str) -> Option<bool> {
+    let current = current.trim_start_matches('v');
+    let target = target.trim_start_matches('v');
+
+    match (Version::parse(current), Version::parse(target)) {
+        (Ok(current), Ok(target)) => Some(target > current),
+        _ if current == target => Some(false),
+        _ => None,
+    }
+}
+
+/// Top level folders of the data.arc, which a mod archived without a folder of its own can start with
+static GAME_ROOTS: &[&str] = &[
+    "append",
+    "assist",
+    "boss",
+    "camera",
+    "common",
+    "effect",
+    "enemy",
+    "fighter",
+    "finalsmash",
+    "item",
+    "miihat",
+    "param",
+    "pokemon",
+    "prebuilt;",
+    "render",
+    "snapshot",
+    "sound",
+    "spirits",
+    "stage",
+    "standard",
+    "stream;",
+    "ui",
+];
+
+/// Archives usually wrap the mod in a folder of its own, in which case that folder is the one to install. A single folder is
+/// only taken for that wrapper if it has an `info.toml`, or isn't named after a folder of the data.arc.
+fn content_root(staging: &Path) -> std::io::Result<PathBuf> {
+    let entries: Vec<PathBuf> = std::fs::read_dir(staging)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+
+    let is_game_root = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| GAME_ROOTS.contains(&name.to_ascii_lowercase().as_str()))
+    };
+
+    match entries.as_slice() {
+        [single] if single.is_dir() && (single.join("info.toml").is_file() || !is_game_root(single)) => Ok(single.clone()),
+        _ => Ok(staging.to_path_buf()),
+    }
+}
+
+/// Moves the installed mod to `backup` and the staged mod in its place. The installed mod is put back if the latter fails.
+fn swap_in(staged: &Path, path: &Path, backup: &Path) -> std::io::Result<()> {
+    // Only the version that was installed right before the update is kept
+    if backup.exists() {
+        std::fs::remove_dir_all(backup)?;
+    }
+
+    if let Some(parent) = backup.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::rename(path, backup)?;
+
+    if let Err(e) = std::fs::rename(staged, path) {
+        error!("Failed to move the update of '{}' into place, restoring the previous version. Reason: {:?}", path.display(), e);
+
+        if let Err(e) = std::fs::rename(backup, path) {
+            error!("Failed to restore '{}' from '{}'. Reason: {:?}", path.display(), backup.display(), e);
+        }
+
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Extracts the release to a staging folder under `updates_path`, then swaps it with the installed mod, which is kept in
+/// a backup folder under `updates_path`
+pub fn install(path: &Path, zip: &[u8], expected_sha256: Option<&str>, updates_path: &Path) -> Result<PathBuf, UpdateError> {
+    let folder_name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+    verify_checksum(&folder_name.to_string_lossy(), zip, expected_sha256)?;
+
+    let staging = updates_path.join("staging").join(&folder_name);
+    let backup = updates_path.join("backup").join(&folder_name);
+
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(zip))?;
+
+    if let Err(e) = archive.extract(&staging) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e.into());
+    }
+
+    let result = content_root(&staging).and_then(|staged| swap_in(&staged, path, &backup));
+    let _ = std::fs::remove_dir_all(&staging);
+    result?;
+
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sha256_hex,
+        testing::{make_zip, TempRoot},
+    };
+
+    #[test]
+    fn semver_versions_are_compared() {
+        assert_eq!(is_newer("1.0.0", "1.1.0"), Some(true));
+        assert_eq!(is_newer("v1.1.0", "1.0.0"), Some(false));
+        assert_eq!(is_newer("1.0.0", "v1.0.0"), Some(false));
+        assert_eq!(is_newer("1.0.0", "1.0.1-beta"), Some(true));
+    }
+
+    #[test]
+    fn other_versions_are_left_to_the_user() {
+        assert_eq!(is_newer("1.2", "1.3"), None);
+        assert_eq!(is_newer("1.3", "1.2"), None);
+        assert_eq!(is_newer("1.0.0", "final"), None);
+        assert_eq!(is_newer("final", "final"), Some(false));
+        assert_eq!(is_newer("v1.2", "1.2"), Some(false));
+    }
+
+    #[test]
+    fn content_root_skips_a_wrapping_folder() {
+        let root = TempRoot::new("mod-wrapped");
+        root.write("SomeMod/fighter/mario/model.nutexb", "");
+
+        assert_eq!(content_root(&root.0).unwrap(), root.0.join("SomeMod"));
+    }
+
+    #[test]
+    fn content_root_keeps_unwrapped_archives() {
+        let root = TempRoot::new("mod-unwrapped");
+        root.write("fighter/mario/model.nutexb", "");
+        root.write("info.toml", "");
+
+        assert_eq!(content_root(&root.0).unwrap(), root.0);
+
+        let root = TempRoot::new("mod-single-file");
+        root.write("info.toml", "");
+
+        assert_eq!(content_root(&root.0).unwrap(), root.0);
+    }
+
+    #[test]
+    fn content_root_keeps_a_single_game_folder() {
+        let root = TempRoot::new("mod-game-folder");
+        root.write("fighter/mario/model.nutexb", "");
+
+        assert_eq!(content_root(&root.0).unwrap(), root.0);
+
+        // A wrapper named after a game folder is still told apart by its info.toml
+        let root = TempRoot::new("mod-named-fighter");
+        root.write("fighter/info.toml", "");
+        root.write("fighter/fighter/mario/model.nutexb", "");
+
+        assert_eq!(content_root(&root.0).unwrap(), root.0.join("fighter"));
+    }
+
+    #[test]
+    fn install_keeps_the_previous_version() {
+        let root = TempRoot::new("mod-install");
+        root.write("mods/SomeMod/old.txt", "1.0.0");
+        let path = root.0.join("mods/SomeMod");
+        let updates_path = root.0.join("mod_updates");
+
+        let zip = make_zip(&[("SomeMod/new.txt", "1.1.0")]);
+        let backup = install(&path, &zip, Some(&sha256_hex(&zip)), &updates_path).unwrap();
+
+        assert_eq!(backup, updates_path.join("backup/SomeMod"));
+        assert_eq!(root.read("mods/SomeMod/new.txt").as_deref(), Some("1.1.0"));
+        assert_eq!(root.read("mods/SomeMod/old.txt"), None);
+        assert_eq!(root.read("mod_updates/backup/SomeMod/old.txt").as_deref(), Some("1.0.0"));
+        assert!(!updates_path.join("staging/SomeMod").exists());
+
+        // Only the version installed right before the latest update is kept
+        let zip = make_zip(&[("newer.txt", "1.2.0")]);
+        install(&path, &zip, None, &updates_path).unwrap();
+
+        assert_eq!(root.read("mods/SomeMod/newer.txt").as_deref(), Some("1.2.0"));
+        assert_eq!(root.read("mod_updates/backup/SomeMod/new.txt").as_deref(), Some("1.1.0"));
+        assert_eq!(root.read("mod_updates/backup/SomeMod/old.txt"), None);
+    }
+
+    #[test]
+    fn checksum_mismatch_leaves_the_mod_unchanged() {
+        let root = TempRoot::new("mod-checksum");
+        root.write("mods/SomeMod/old.txt", "1.0.0");
+        let updates_path = root.0.join("mod_updates");
+
+        let zip = make_zip(&[("SomeMod/new.txt", "1.1.0")]);
+        let result = install(&root.0.join("mods/SomeMod"), &zip, Some(&sha256_hex(b"something else")), &updates_path);
+
+        assert!(matches!(result, Err(UpdateError::Checksum { .. })));
+        assert_eq!(root.read("mods/SomeMod/old.txt").as_deref(), Some("1.0.0"));
+        assert!(!updates_path.exists());
+    }
+
+    #[test]
+    fn failed_swap_restores_the_previous_version() {
+        let root = TempRoot::new("mod-restore");
+        root.write("mods/SomeMod/old.txt", "1.0.0");
+        let path = root.0.join("mods/SomeMod");
+        let backup = root.0.join("mod_updates/backup/SomeMod");
+
+        // The staged folder is gone by the time it should be moved into place
+        assert!(swap_in(&root.0.join("mod_updates/staging/SomeMod"), &path, &backup).is_err());
+
+        assert_eq!(root.read("mods/SomeMod/old.txt").as_deref(), Some("1.0.0"));
+        assert!(!backup.exists());
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::UpdateError;
+
+/// A release that was found, but not downloaded yet
+pub trait Release {
+    fn tag(&self) -> &str;
+
+    /// The release notes, in the same format as the `changelog.toml` shipped in the release
+    fn changelog(&self) -> Option<String>;
+
+    /// Downloads the release asset along with the sha256 it is expected to have, if the release published one
+    fn download(&self) -> Result<(Vec<u8>, Option<String>), UpdateError>;
+}
+
+/// Where updates are looked for
+pub trait ReleaseSource {
+    fn find_latest(&self, beta_enabled: bool) -> Result<Option<Box<dyn Release>>, UpdateError>;
+
+    /// Looks for the release published under a tag, with or without its `v` prefix, be it older or newer than the latest one
+    fn find_tag(&self, tag: &str) -> Result<Option<Box<dyn Release>>, UpdateError>;
+}
+
+/// Takes the hash out of a checksum file, which can either be the bare hash or the `sha256sum` format
+pub fn parse_checksum(data: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(data).ok()?;
+    text.split_whitespace().next().map(|hash| hash.to_lowercase())
+}
+
+/// A release sitting in a folder, made of the release asset, its optional `.sha256` and a `version.txt` holding the tag.
+/// Useful to install an update without an internet connection, or to try the update process against a fake release.
+pub struct LocalSource {
+    pub path: PathBuf,
+    pub asset: String,
+}
+
+struct LocalRelease {
+    tag: String,
+    path: PathBuf,
+    asset: String,
+}
+
+impl Release for LocalRelease {
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn changelog(&self) -> Option<String> {
+        std::fs::read_to_string(self.path.join("changelog.toml")).ok()
+    }
+
+    fn download(&self) -> Result<(Vec<u8>, Option<String>), UpdateError> {
+        let checksum = std::fs::read(self.path.join(format!("{}.sha256", self.asset)))
+            .ok()
+            .and_then(|data| parse_checksum(&data));
+        let zip = std::fs::read(self.path.join(&self.asset)).map_err(|_| UpdateError::MissingAsset(self.asset.clone()))?;
+
+        Ok((zip, checksum))
+    }
+}
+
+impl ReleaseSource for LocalSource {
+    fn find_latest(&self, _beta_enabled: bool) -> Result<Option<Box<dyn Release>>, UpdateError> {
+        let tag = match std::fs::read_to_string(self.path.join("version.txt")) {
+            Ok(tag) => tag.trim().trim_start_matches('v').to_string(),
+            Err(_) => return Ok(None),
+        };
+
+        Ok(Some(Box::new(LocalRelease {
+            tag,
+            path: self.path.clone(),
+            asset: self.asset.clone(),
+        })))
+    }
+
+    /// The folder only ever holds one release
+    fn find_tag(&self, tag: &str) -> Result<Option<Box<dyn Release>>, UpdateError> {
+        let tag = tag.trim_start_matches('v');
+
+        Ok(self.find_latest(true)?.filter(|release| release.tag() == tag))
+    }
+}
@@ -0,0 +1,46 @@
+//! Helpers for the tests of the updaters, which run on the host
+
+use std::{
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+
+/// A folder in the system's temporary folder standing in for `sd:/`, removed once the test is over
+pub struct TempRoot(pub PathBuf);
+
+impl TempRoot {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("arcropolis-update-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn write(&self, file: &str, contents: &str) {
+        let path = self.0.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    pub fn read(&self, file: &str) -> Option<String> {
+        std::fs::read_to_string(self.0.join(file)).ok()
+    }
+}
+
+impl Drop for TempRoot {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn make_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    for (name, contents) in files {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(contents.as_bytes()).unwrap();
+    }
+
+    writer.finish().unwrap().into_inner()
+}